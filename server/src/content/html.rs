@@ -0,0 +1,150 @@
+//! Renders a `Document` to HTML. Every piece of text is escaped and only
+//! harmless URL schemes are let through, so the output can be embedded as is.
+
+use super::{Block, Document, Inline, Span, Text};
+
+pub fn escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Relative URLs and `http`, `https` and `mailto` ones, anything else such as
+/// `javascript:` is refused.
+pub fn is_safe_url(url: &str) -> bool {
+    let scheme = url
+        .trim()
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| !scheme.contains(['/', '?', '#']));
+    matches!(
+        scheme.map(|v| v.to_ascii_lowercase()).as_deref(),
+        None | Some("http") | Some("https") | Some("mailto")
+    )
+}
+
+fn safe_url(url: &str) -> Option<String> {
+    match is_safe_url(url) {
+        true => Some(escape(url.trim())),
+        false => None,
+    }
+}
+
+fn render_span(span: &Span, out: &mut String) {
+    let mut open = Vec::new();
+    if let Some(href) = span.link.as_deref().and_then(safe_url) {
+        out.push_str(&format!("<a href=\"{}\" rel=\"noopener\">", href));
+        open.push("</a>");
+    }
+    for (on, start, end) in [
+        (span.bold, "<strong>", "</strong>"),
+        (span.italic, "<em>", "</em>"),
+        (span.code, "<code>", "</code>"),
+    ] {
+        if on {
+            out.push_str(start);
+            open.push(end);
+        }
+    }
+    out.push_str(&escape(&span.text));
+    for end in open.into_iter().rev() {
+        out.push_str(end);
+    }
+}
+
+fn render_text(text: &Text, out: &mut String) {
+    match text {
+        Text::Plain(v) => out.push_str(&escape(v)),
+        Text::Rich(items) => {
+            for item in items {
+                match item {
+                    Inline::Plain(v) => out.push_str(&escape(v)),
+                    Inline::Span(span) => render_span(span, out),
+                }
+            }
+        }
+    }
+}
+
+fn render_block(block: &Block, out: &mut String) {
+    match block {
+        Block::Paragraph { text } => {
+            out.push_str("<p>");
+            render_text(text, out);
+            out.push_str("</p>");
+        }
+        Block::Heading { level, text } => {
+            let level = (*level).clamp(1, 6);
+            out.push_str(&format!("<h{}>", level));
+            render_text(text, out);
+            out.push_str(&format!("</h{}>", level));
+        }
+        Block::Code { language, code } => {
+            let language = language.as_deref().map(|v| {
+                v.chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+'))
+                    .collect::<String>()
+            });
+            match language.filter(|v| !v.is_empty()) {
+                Some(v) => out.push_str(&format!("<pre><code class=\"language-{}\">", v)),
+                None => out.push_str("<pre><code>"),
+            }
+            out.push_str(&escape(code));
+            out.push_str("</code></pre>");
+        }
+        Block::Image { src, alt, caption } => {
+            let src = match safe_url(src) {
+                Some(v) => v,
+                None => return,
+            };
+            out.push_str("<figure>");
+            out.push_str(&format!(
+                "<img src=\"{}\" alt=\"{}\" loading=\"lazy\">",
+                src,
+                escape(alt.as_deref().unwrap_or_default())
+            ));
+            if let Some(caption) = caption {
+                out.push_str(&format!("<figcaption>{}</figcaption>", escape(caption)));
+            }
+            out.push_str("</figure>");
+        }
+        Block::Quote { text, cite } => {
+            out.push_str("<blockquote><p>");
+            render_text(text, out);
+            out.push_str("</p>");
+            if let Some(cite) = cite {
+                out.push_str(&format!("<cite>{}</cite>", escape(cite)));
+            }
+            out.push_str("</blockquote>");
+        }
+        Block::List { ordered, items } => {
+            let tag = if *ordered { "ol" } else { "ul" };
+            out.push_str(&format!("<{}>", tag));
+            for item in items {
+                out.push_str("<li>");
+                render_text(item, out);
+                out.push_str("</li>");
+            }
+            out.push_str(&format!("</{}>", tag));
+        }
+        Block::Unknown => {}
+    }
+}
+
+pub fn render(doc: &Document) -> String {
+    let mut out = String::new();
+    for block in doc.blocks.iter() {
+        render_block(block, &mut out);
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,136 @@
+//! The block schema of `post.content`.
+//!
+//! ```json
+//! {
+//!   "blocks": [
+//!     { "type": "heading", "level": 2, "text": "Hello" },
+//!     { "type": "paragraph", "text": ["plain ", { "text": "bold", "bold": true }] },
+//!     { "type": "code", "language": "rust", "code": "fn main() {}" },
+//!     { "type": "image", "src": "/a.png", "alt": "a", "caption": "A" },
+//!     { "type": "quote", "text": "...", "cite": "someone" },
+//!     { "type": "list", "ordered": false, "items": ["one", "two"] }
+//!   ]
+//! }
+//! ```
+//!
+//! Text is either a string or a list of strings and spans, a span may be
+//! `bold`, `italic`, `code` and carry a `link`. Writes are checked against the
+//! schema by `validate`, which refuses blocks of an unknown type. Content
+//! stored before that may still hold them, the renderers skip those.
+
+pub mod html;
+pub mod stats;
+pub mod text;
+pub mod validate;
+
+use serde::{Deserialize, Serialize};
+use serde_enum_str::Deserialize_enum_str;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Document {
+    #[serde(default)]
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Block {
+    Paragraph {
+        text: Text,
+    },
+    Heading {
+        #[serde(default = "default_level")]
+        level: u8,
+        text: Text,
+    },
+    Code {
+        #[serde(default)]
+        language: Option<String>,
+        code: String,
+    },
+    Image {
+        src: String,
+        #[serde(default)]
+        alt: Option<String>,
+        #[serde(default)]
+        caption: Option<String>,
+    },
+    Quote {
+        text: Text,
+        #[serde(default)]
+        cite: Option<String>,
+    },
+    List {
+        #[serde(default)]
+        ordered: bool,
+        items: Vec<Text>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+fn default_level() -> u8 {
+    2
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Text {
+    Plain(String),
+    Rich(Vec<Inline>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Inline {
+    Plain(String),
+    Span(Span),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Span {
+    pub text: String,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub code: bool,
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
+impl Document {
+    pub fn from_value(value: &serde_json::Value) -> Result<Self, serde_json::Error> {
+        Document::deserialize(value)
+    }
+}
+
+/// How `content` is returned by the post endpoints.
+#[derive(Debug, Clone, Default, Deserialize_enum_str)]
+#[serde(rename_all = "camelCase")]
+pub enum ContentFormat {
+    #[default]
+    Json,
+    Html,
+    Text,
+}
+
+impl ContentFormat {
+    /// Renders the stored content, `Json` leaves it untouched.
+    pub fn render(
+        &self,
+        content: Option<serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>, serde_json::Error> {
+        let content = match (self, content) {
+            (ContentFormat::Json, content) | (_, content @ None) => return Ok(content),
+            (_, Some(content)) => content,
+        };
+        let doc = Document::from_value(&content)?;
+        let rendered = match self {
+            ContentFormat::Html => html::render(&doc),
+            _ => text::render(&doc),
+        };
+        Ok(Some(serde_json::Value::String(rendered)))
+    }
+}
@@ -0,0 +1,86 @@
+//! Figures derived from a `Document` when a post is saved.
+
+use super::{text::text_of, Block, Document};
+
+/// reading speed for space separated scripts, in words per minute
+const WORDS_PER_MINUTE: f64 = 200.0;
+/// reading speed for Chinese and Japanese, in characters per minute
+const CJK_CHARS_PER_MINUTE: f64 = 350.0;
+const EXCERPT_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// words of space separated scripts plus CJK characters, which are
+    /// counted one by one as they are not separated by spaces
+    pub word_count: i32,
+    /// estimated reading time in minutes, at least one for any text
+    pub reading_time: i32,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' // hiragana and katakana
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}')
+}
+
+pub fn count(text: &str) -> Stats {
+    let (mut words, mut cjk) = (0, 0);
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            cjk += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+            }
+            in_word = true;
+        } else if !(in_word && matches!(c, '\'' | '’' | '-')) {
+            in_word = false;
+        }
+    }
+    let minutes = words as f64 / WORDS_PER_MINUTE + cjk as f64 / CJK_CHARS_PER_MINUTE;
+    let word_count = words + cjk;
+    Stats {
+        word_count,
+        reading_time: match word_count {
+            0 => 0,
+            _ => (minutes.ceil() as i32).max(1),
+        },
+    }
+}
+
+/// The opening prose of the document cut at a word boundary where possible,
+/// `None` if it has no paragraphs.
+pub fn excerpt(doc: &Document) -> Option<String> {
+    let prose = doc
+        .blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Paragraph { text } => Some(text_of(text)),
+            _ => None,
+        })
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if prose.is_empty() {
+        return None;
+    }
+    if prose.chars().count() <= EXCERPT_CHARS {
+        return Some(prose);
+    }
+    let cut: String = prose.chars().take(EXCERPT_CHARS).collect();
+    // CJK text has no spaces to cut at, a cut mid-sentence is fine there
+    let cut = match cut.rfind(' ') {
+        Some(i) if !cut.chars().last().is_some_and(is_cjk) && i > cut.len() / 2 => &cut[..i],
+        _ => cut.as_str(),
+    };
+    Some(format!(
+        "{}…",
+        cut.trim_end_matches(|c: char| !c.is_alphanumeric())
+    ))
+}
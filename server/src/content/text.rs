@@ -0,0 +1,50 @@
+//! Plain-text projection of a `Document`, for feeds, emails and excerpts.
+
+use super::{Block, Document, Inline, Text};
+
+pub fn text_of(text: &Text) -> String {
+    match text {
+        Text::Plain(v) => v.clone(),
+        Text::Rich(items) => items
+            .iter()
+            .map(|item| match item {
+                Inline::Plain(v) => v.as_str(),
+                Inline::Span(span) => span.text.as_str(),
+            })
+            .collect(),
+    }
+}
+
+fn render_block(block: &Block) -> Option<String> {
+    match block {
+        Block::Paragraph { text } | Block::Heading { text, .. } => Some(text_of(text)),
+        Block::Code { code, .. } => Some(code.clone()),
+        Block::Image { alt, caption, .. } => caption.clone().or_else(|| alt.clone()),
+        Block::Quote { text, cite } => Some(match cite {
+            Some(cite) => format!("\"{}\" - {}", text_of(text), cite),
+            None => format!("\"{}\"", text_of(text)),
+        }),
+        Block::List { ordered, items } => Some(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| match ordered {
+                    true => format!("{}. {}", i + 1, text_of(item)),
+                    false => format!("- {}", text_of(item)),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        Block::Unknown => None,
+    }
+}
+
+/// Blocks are separated by a blank line.
+pub fn render(doc: &Document) -> String {
+    doc.blocks
+        .iter()
+        .filter_map(render_block)
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
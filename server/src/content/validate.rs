@@ -0,0 +1,226 @@
+//! Checks a `content` document against the block schema before it is stored,
+//! reporting every violation with the JSON pointer of the offending value.
+
+use serde_json::{Map, Value};
+
+use crate::core::error::FieldError;
+
+const MAX_DOCUMENT_BYTES: usize = 2 * 1024 * 1024;
+const MAX_BLOCKS: usize = 5000;
+const MAX_ITEMS: usize = 1000;
+const MAX_INLINES: usize = 1000;
+const MAX_TEXT_CHARS: usize = 64 * 1024;
+const MAX_URL_CHARS: usize = 2048;
+const MAX_LANGUAGE_CHARS: usize = 32;
+/// stop collecting once this many violations were found
+const MAX_ERRORS: usize = 50;
+
+struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        if self.errors.len() < MAX_ERRORS {
+            self.errors.push(FieldError {
+                path: path.to_owned(),
+                message: message.into(),
+            });
+        }
+    }
+
+    fn string(&mut self, path: &str, value: &Value, max: usize) -> Option<String> {
+        match value.as_str() {
+            Some(v) if v.chars().count() > max => {
+                self.error(path, format!("must be at most {} characters.", max));
+                None
+            }
+            Some(v) => Some(v.to_owned()),
+            None => {
+                self.error(path, "must be a string.");
+                None
+            }
+        }
+    }
+
+    fn optional_string(&mut self, obj: &Map<String, Value>, path: &str, key: &str, max: usize) {
+        if let Some(v) = obj.get(key).filter(|v| !v.is_null()) {
+            self.string(&format!("{}/{}", path, key), v, max);
+        }
+    }
+
+    fn optional_bool(&mut self, obj: &Map<String, Value>, path: &str, key: &str) {
+        if let Some(v) = obj.get(key).filter(|v| !v.is_null()) {
+            if !v.is_boolean() {
+                self.error(&format!("{}/{}", path, key), "must be a boolean.");
+            }
+        }
+    }
+
+    fn url(&mut self, path: &str, value: &Value) {
+        if let Some(url) = self.string(path, value, MAX_URL_CHARS) {
+            if url.trim().is_empty() {
+                self.error(path, "must not be empty.");
+            } else if !super::html::is_safe_url(&url) {
+                self.error(path, "must be a relative, http(s) or mailto URL.");
+            }
+        }
+    }
+
+    fn required<'a>(
+        &mut self,
+        obj: &'a Map<String, Value>,
+        path: &str,
+        key: &str,
+    ) -> Option<&'a Value> {
+        let value = obj.get(key).filter(|v| !v.is_null());
+        if value.is_none() {
+            self.error(&format!("{}/{}", path, key), "is required.");
+        }
+        value
+    }
+
+    fn span(&mut self, path: &str, obj: &Map<String, Value>) {
+        if let Some(text) = self.required(obj, path, "text") {
+            // spans do not nest, a span's text is always plain
+            self.string(&format!("{}/text", path), text, MAX_TEXT_CHARS);
+        }
+        for key in ["bold", "italic", "code"] {
+            self.optional_bool(obj, path, key);
+        }
+        if let Some(link) = obj.get("link").filter(|v| !v.is_null()) {
+            self.url(&format!("{}/link", path), link);
+        }
+    }
+
+    fn text(&mut self, path: &str, value: &Value) {
+        match value {
+            Value::String(_) => {
+                self.string(path, value, MAX_TEXT_CHARS);
+            }
+            Value::Array(items) if items.len() > MAX_INLINES => {
+                self.error(path, format!("must have at most {} items.", MAX_INLINES))
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    let path = format!("{}/{}", path, i);
+                    match item {
+                        Value::String(_) => {
+                            self.string(&path, item, MAX_TEXT_CHARS);
+                        }
+                        Value::Object(obj) if obj.contains_key("type") => {
+                            self.error(&path, "blocks can not be nested in text.")
+                        }
+                        Value::Object(obj) => self.span(&path, obj),
+                        _ => self.error(&path, "must be a string or a span."),
+                    }
+                }
+            }
+            _ => self.error(path, "must be a string or a list of spans."),
+        }
+    }
+
+    fn block(&mut self, path: &str, value: &Value) {
+        let obj = match value.as_object() {
+            Some(v) => v,
+            None => return self.error(path, "must be an object."),
+        };
+        let kind = match obj.get("type").and_then(Value::as_str) {
+            Some(v) => v,
+            None => return self.error(&format!("{}/type", path), "is required."),
+        };
+        match kind {
+            "paragraph" => {
+                if let Some(text) = self.required(obj, path, "text") {
+                    self.text(&format!("{}/text", path), text);
+                }
+            }
+            "heading" => {
+                if let Some(level) = obj.get("level").filter(|v| !v.is_null()) {
+                    if !level.as_u64().is_some_and(|v| (1..=6).contains(&v)) {
+                        self.error(&format!("{}/level", path), "must be between 1 and 6.");
+                    }
+                }
+                if let Some(text) = self.required(obj, path, "text") {
+                    self.text(&format!("{}/text", path), text);
+                }
+            }
+            "code" => {
+                if let Some(code) = self.required(obj, path, "code") {
+                    self.string(&format!("{}/code", path), code, MAX_TEXT_CHARS);
+                }
+                self.optional_string(obj, path, "language", MAX_LANGUAGE_CHARS);
+            }
+            "image" => {
+                if let Some(src) = self.required(obj, path, "src") {
+                    self.url(&format!("{}/src", path), src);
+                }
+                self.optional_string(obj, path, "alt", MAX_TEXT_CHARS);
+                self.optional_string(obj, path, "caption", MAX_TEXT_CHARS);
+            }
+            "quote" => {
+                if let Some(text) = self.required(obj, path, "text") {
+                    self.text(&format!("{}/text", path), text);
+                }
+                self.optional_string(obj, path, "cite", MAX_TEXT_CHARS);
+            }
+            "list" => {
+                self.optional_bool(obj, path, "ordered");
+                let items = match self.required(obj, path, "items") {
+                    Some(v) => v,
+                    None => return,
+                };
+                let path = format!("{}/items", path);
+                match items.as_array() {
+                    Some(items) if items.len() > MAX_ITEMS => {
+                        self.error(&path, format!("must have at most {} items.", MAX_ITEMS))
+                    }
+                    Some(items) => {
+                        for (i, item) in items.iter().enumerate() {
+                            self.text(&format!("{}/{}", path, i), item);
+                        }
+                    }
+                    None => self.error(&path, "must be a list."),
+                }
+            }
+            _ => self.error(
+                &format!("{}/type", path),
+                format!("unknown block type `{}`.", kind),
+            ),
+        }
+    }
+}
+
+/// Validates `value` as the `content` of a post, paths are prefixed with
+/// `/content`.
+pub fn validate(value: &Value) -> Result<(), Vec<FieldError>> {
+    let mut v = Validator { errors: Vec::new() };
+    let root = "/content";
+    if value.to_string().len() > MAX_DOCUMENT_BYTES {
+        v.error(
+            root,
+            format!("must be at most {} bytes.", MAX_DOCUMENT_BYTES),
+        );
+        return Err(v.errors);
+    }
+    match value.as_object() {
+        Some(obj) => match obj.get("blocks").map(Value::as_array) {
+            Some(Some(blocks)) if blocks.len() > MAX_BLOCKS => v.error(
+                &format!("{}/blocks", root),
+                format!("must have at most {} blocks.", MAX_BLOCKS),
+            ),
+            Some(Some(blocks)) => {
+                for (i, block) in blocks.iter().enumerate() {
+                    v.block(&format!("{}/blocks/{}", root, i), block);
+                }
+            }
+            Some(None) => v.error(&format!("{}/blocks", root), "must be a list."),
+            None => v.error(&format!("{}/blocks", root), "is required."),
+        },
+        None => v.error(root, "must be an object."),
+    }
+    match v.errors.is_empty() {
+        true => Ok(()),
+        false => Err(v.errors),
+    }
+}
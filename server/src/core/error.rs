@@ -1,28 +1,44 @@
-use axum::{http::StatusCode, Json};
+use axum::{
+    http::{header::HeaderName, HeaderValue, StatusCode},
+    Json,
+};
 use std::{error::Error, fmt::Display};
 
 use crate::core::response::ResponseBody;
 use sea_orm::TransactionError;
+use serde::Serialize;
 use serde_repr::Serialize_repr;
 
 #[derive(Clone, Serialize_repr, PartialEq, Debug)]
 #[repr(u16)]
 pub enum ErrorCode {
     OK = 2000,
+    MovedPermanently = 3010,
     InvalidRequest = 4000,
     Forbidden = 4010,
     InvalidToken = 4011,
     ExpiredToken = 4012,
+    InvalidCredentials = 4013,
+    RevokedToken = 4014,
     NotFound = 4040,
+    PreconditionFailed = 4120,
+    ValidationFailed = 4220,
+    PreconditionRequired = 4280,
+    TooManyAttempts = 4290,
     UnkownError = 5000,
     DBError = 5001,
     TokenCreation = 5002,
     DataParsingError = 5003,
+    IdentityProvider = 5004,
 }
 
 impl ErrorCode {
     pub fn res(&self) -> (StatusCode, String) {
         match self {
+            ErrorCode::MovedPermanently => (
+                StatusCode::MOVED_PERMANENTLY,
+                "resource has moved permanently.".to_owned(),
+            ),
             ErrorCode::InvalidRequest => (
                 StatusCode::BAD_REQUEST,
                 "invalid request, please check request payload or headers.".to_owned(),
@@ -30,10 +46,34 @@ impl ErrorCode {
             ErrorCode::Forbidden => (StatusCode::UNAUTHORIZED, "forbidden.".to_owned()),
             ErrorCode::InvalidToken => (StatusCode::UNAUTHORIZED, "invalid token.".to_owned()),
             ErrorCode::ExpiredToken => (StatusCode::UNAUTHORIZED, "token is expired.".to_owned()),
+            ErrorCode::RevokedToken => (
+                StatusCode::UNAUTHORIZED,
+                "token has been revoked.".to_owned(),
+            ),
+            ErrorCode::InvalidCredentials => (
+                StatusCode::UNAUTHORIZED,
+                "email or password is incorrect.".to_owned(),
+            ),
             ErrorCode::NotFound => (
                 StatusCode::NOT_FOUND,
                 "resources does not exist.".to_owned(),
             ),
+            ErrorCode::PreconditionFailed => (
+                StatusCode::PRECONDITION_FAILED,
+                "resource has been changed by someone else, please reload it.".to_owned(),
+            ),
+            ErrorCode::PreconditionRequired => (
+                StatusCode::PRECONDITION_REQUIRED,
+                "If-Match header with the ETag of the resource is required.".to_owned(),
+            ),
+            ErrorCode::ValidationFailed => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation failed, see data for the offending fields.".to_owned(),
+            ),
+            ErrorCode::TooManyAttempts => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "too many attempts, please try again later.".to_owned(),
+            ),
             ErrorCode::UnkownError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "unknown error.".to_owned(),
@@ -47,16 +87,30 @@ impl ErrorCode {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "data parsing error.".to_owned(),
             ),
+            ErrorCode::IdentityProvider => (
+                StatusCode::BAD_GATEWAY,
+                "identity provider error.".to_owned(),
+            ),
             _ => (StatusCode::OK, "success".to_owned()),
         }
     }
 }
 
+/// One violation of a payload, `path` is a JSON pointer into it.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub path: String,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub struct AppError {
     pub msg: Option<String>,
     pub code: ErrorCode,
     pub source: Option<Box<dyn Error>>,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    /// returned as `data` of the error response
+    pub details: Option<serde_json::Value>,
 }
 
 impl AppError {
@@ -64,6 +118,8 @@ impl AppError {
         Self {
             msg,
             source: Some(err),
+            headers: Vec::new(),
+            details: None,
             code: match code {
                 Some(code) => code,
                 _ => ErrorCode::UnkownError,
@@ -75,6 +131,8 @@ impl AppError {
         Self {
             msg: Some(msg),
             source: None,
+            headers: Vec::new(),
+            details: None,
             code: match code {
                 Some(code) => code,
                 _ => ErrorCode::UnkownError,
@@ -87,8 +145,21 @@ impl AppError {
             msg,
             code,
             source: None,
+            headers: Vec::new(),
+            details: None,
         }
     }
+
+    pub fn validation(errors: Vec<FieldError>) -> Self {
+        let mut err = Self::from_code(ErrorCode::ValidationFailed, None);
+        err.details = serde_json::to_value(errors).ok();
+        err
+    }
+
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.push((name, value));
+        self
+    }
 }
 
 impl Display for AppError {
@@ -141,7 +212,14 @@ impl axum::response::IntoResponse for AppError {
         if let Some(err) = self.source {
             println!("error fired: {:#?}", err);
         }
-        (http_code, Json(ResponseBody::error(self.code, msg))).into_response()
+        let mut res = match self.details {
+            Some(details) => {
+                (http_code, Json(ResponseBody::new(details, self.code, msg))).into_response()
+            }
+            None => (http_code, Json(ResponseBody::error(self.code, msg))).into_response(),
+        };
+        res.headers_mut().extend(self.headers);
+        res
     }
 }
 
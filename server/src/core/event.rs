@@ -0,0 +1,18 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Something other subsystems (feeds, caches, webhooks) may react to.
+/// Subscribe with `AppState::events.subscribe()`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    #[serde(rename_all = "camelCase")]
+    PostPublished { id: i64, route: Option<String> },
+}
+
+/// events are dropped for subscribers which lag this far behind
+const CAPACITY: usize = 256;
+
+pub fn channel() -> broadcast::Sender<Event> {
+    broadcast::channel(CAPACITY).0
+}
@@ -1,4 +1,5 @@
 pub mod error;
+pub mod event;
 pub mod response;
 
 use std::sync::Arc;
@@ -8,6 +9,10 @@ use serde::Deserialize;
 #[derive(Deserialize, Debug)]
 pub struct ServerConfig {
     pub addr: std::net::SocketAddr,
+    /// take the client address from the last `X-Forwarded-For` hop, only enable
+    /// it behind a single reverse proxy which appends to the header
+    #[serde(default)]
+    pub trust_proxy: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -20,8 +25,38 @@ pub struct PGConfig {
 
 #[derive(Deserialize, Debug)]
 pub struct JWTConfig {
+    #[serde(default)]
     pub secret: String,
     pub expires: i64,
+    #[serde(default = "JWTConfig::default_refresh_expires")]
+    pub refresh_expires: i64,
+    /// `HS256` (default), `RS256` or `EdDSA`
+    #[serde(default = "JWTConfig::default_algorithm")]
+    pub algorithm: String,
+    pub key_dir: Option<String>,
+    pub kid: Option<String>,
+}
+
+impl JWTConfig {
+    fn default_algorithm() -> String {
+        "HS256".to_owned()
+    }
+
+    fn default_refresh_expires() -> i64 {
+        60 * 60 * 24 * 30
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OIDCConfig {
+    /// issuer URL, `/.well-known/openid-configuration` is resolved against it
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+    /// create an author account for unknown users instead of refusing them
+    #[serde(default)]
+    pub auto_create: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -29,6 +64,7 @@ pub struct AppConfig {
     pub server: ServerConfig,
     pub pg: PGConfig,
     pub jwt: JWTConfig,
+    pub oidc: Option<OIDCConfig>,
 }
 
 impl AppConfig {
@@ -46,6 +82,21 @@ impl AppConfig {
 
 pub struct AppState {
     pub db: sea_orm::DatabaseConnection,
+    pub events: tokio::sync::broadcast::Sender<event::Event>,
+}
+
+impl AppState {
+    pub fn new(db: sea_orm::DatabaseConnection) -> Self {
+        Self {
+            db,
+            events: event::channel(),
+        }
+    }
+
+    /// Broadcasts an event, it is fine for nobody to be listening.
+    pub fn emit(&self, event: event::Event) {
+        let _ = self.events.send(event);
+    }
 }
 
 use once_cell::sync::Lazy;
@@ -1,4 +1,8 @@
-use axum::Json;
+use axum::{
+    http::{header, HeaderValue},
+    response::IntoResponse,
+    Json,
+};
 use serde::Serialize;
 
 use super::error::ErrorCode;
@@ -53,6 +57,24 @@ impl ResponseBody<()> {
 
 pub type HandlerResult<T> = Result<Json<ResponseBody<T>>, crate::core::error::AppError>;
 
+/// A response carrying the `ETag` of the resource it returns.
+pub struct Tagged<T: Serialize> {
+    pub etag: String,
+    pub body: Json<ResponseBody<T>>,
+}
+
+impl<T: Serialize> IntoResponse for Tagged<T> {
+    fn into_response(self) -> axum::response::Response {
+        let mut res = self.body.into_response();
+        if let Ok(value) = HeaderValue::from_str(&self.etag) {
+            res.headers_mut().insert(header::ETAG, value);
+        }
+        res
+    }
+}
+
+pub type TaggedResult<T> = Result<Tagged<T>, crate::core::error::AppError>;
+
 #[macro_export]
 macro_rules! res_ok {
     ($data: expr) => {
@@ -65,6 +87,16 @@ macro_rules! res_ok {
     };
 }
 
+#[macro_export]
+macro_rules! res_tagged {
+    ($etag: expr, $data: expr) => {
+        Ok($crate::core::response::Tagged {
+            etag: $etag,
+            body: axum::Json($crate::core::response::ResponseBody::ok($data)),
+        })
+    };
+}
+
 #[macro_export]
 macro_rules! res {
     ($data: expr) => {
@@ -12,6 +12,7 @@ pub struct Comment {
     pub parent: Option<Box<Comment>>,
     pub children: Vec<Comment>,
     pub role: UserRole,
+    pub trashed_at: Option<DateTime<Utc>>,
     pub is_authed: bool,
 }
 
@@ -48,6 +49,7 @@ impl From<entity::comment::Model> for Comment {
                 .into_iter()
                 .map(|child| Comment::from(child))
                 .collect(),
+            trashed_at: item.trashed_at,
             is_authed: false,
         }
     }
@@ -70,6 +72,7 @@ impl Serialize for Comment {
         if self.is_authed {
             state.serialize_field("modified", &self.modified)?;
             state.serialize_field("status", &self.status)?;
+            state.serialize_field("trashedAt", &self.trashed_at)?;
         }
         state.end()
     }
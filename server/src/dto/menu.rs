@@ -0,0 +1,24 @@
+use serde::Serialize;
+
+use crate::entity::{menu, menu_item::MenuItemKind, taxonomy::TaxonomyType};
+
+/// A menu item with its target looked up, ready to be rendered.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuLink {
+    pub id: i64,
+    pub kind: MenuItemKind,
+    pub label: String,
+    pub target_id: Option<i64>,
+    /// the nested path of a page, see `GET /posts/path/*path`
+    pub path: Option<String>,
+    pub taxonomy_type: Option<TaxonomyType>,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MenuWithItems {
+    #[serde(flatten)]
+    pub menu: menu::Model,
+    pub items: Vec<MenuLink>,
+}
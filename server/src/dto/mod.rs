@@ -1,3 +1,4 @@
 pub mod comment;
+pub mod menu;
 pub mod post;
 pub mod taxonomy;
@@ -1,6 +1,9 @@
 use crate::{
     dto::taxonomy::{ClassifiedTaxonomy, ClassifyTaxonomy},
-    entity::post::{self, PostStatus},
+    entity::{
+        post::{self, PostStatus},
+        post_draft,
+    },
 };
 use chrono::{DateTime, Utc};
 use serde::{ser::SerializeStruct, Serialize};
@@ -18,9 +21,15 @@ pub struct SimplePost {
     pub excerpts: Option<String>,
     pub route: Option<String>,
     pub is_page: Option<bool>,
+    pub parent_id: Option<i64>,
+    pub sort_order: i32,
     pub status: Option<PostStatus>,
     pub extra: Option<serde_json::Value>,
     pub comment_count: usize,
+    pub word_count: i32,
+    pub reading_time: i32,
+    pub auto_excerpts: Option<String>,
+    pub trashed_at: Option<DateTime<Utc>>,
     pub is_authed: bool,
 }
 
@@ -36,9 +45,15 @@ impl From<post::Model> for SimplePost {
             excerpts: model.excerpts,
             route: model.route,
             is_page: model.is_page,
+            parent_id: model.parent_id,
+            sort_order: model.sort_order,
             status: model.status,
             extra: model.extra,
             comment_count: model.comment_count,
+            word_count: model.word_count,
+            reading_time: model.reading_time,
+            auto_excerpts: model.auto_excerpts,
+            trashed_at: model.trashed_at,
             is_authed: false,
         }
     }
@@ -49,20 +64,28 @@ impl Serialize for SimplePost {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("SimplePost", 12)?;
+        let mut state = serializer.serialize_struct("SimplePost", 15)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("title", &self.title)?;
         state.serialize_field("subtitle", &self.subtitle)?;
         state.serialize_field("published", &self.published)?;
         state.serialize_field("excerpts", &self.excerpts)?;
+        state.serialize_field("autoExcerpts", &self.auto_excerpts)?;
+        state.serialize_field("wordCount", &self.word_count)?;
+        state.serialize_field("readingTime", &self.reading_time)?;
         state.serialize_field("extra", &self.extra)?;
         state.serialize_field("commentCount", &self.comment_count)?;
+        if self.is_page == Some(true) {
+            state.serialize_field("parentId", &self.parent_id)?;
+            state.serialize_field("sortOrder", &self.sort_order)?;
+        }
         if self.is_authed {
             state.serialize_field("created", &self.created)?;
             state.serialize_field("modified", &self.modified)?;
             state.serialize_field("route", &self.route)?;
             state.serialize_field("isPage", &self.is_page)?;
             state.serialize_field("status", &self.status)?;
+            state.serialize_field("trashedAt", &self.trashed_at)?;
         }
         state.end()
     }
@@ -81,9 +104,18 @@ pub struct FulledPost {
     pub content: Option<serde_json::Value>,
     pub route: Option<String>,
     pub is_page: Option<bool>,
+    pub parent_id: Option<i64>,
+    pub sort_order: i32,
     pub status: Option<PostStatus>,
     pub extra: Option<serde_json::Value>,
     pub comment_count: usize,
+    pub word_count: i32,
+    pub reading_time: i32,
+    pub auto_excerpts: Option<String>,
+    pub trashed_at: Option<DateTime<Utc>>,
+    /// the unpublished autosave, only serialized for authed callers; it is not
+    /// covered by the ETag of the post, compare its `modified` instead
+    pub draft: Option<post_draft::Model>,
     pub is_authed: bool,
 }
 
@@ -100,9 +132,16 @@ impl From<post::Model> for FulledPost {
             content: model.content,
             route: model.route,
             is_page: model.is_page,
+            parent_id: model.parent_id,
+            sort_order: model.sort_order,
             status: model.status,
             extra: model.extra,
             comment_count: model.comment_count,
+            word_count: model.word_count,
+            reading_time: model.reading_time,
+            auto_excerpts: model.auto_excerpts,
+            trashed_at: model.trashed_at,
+            draft: None,
             is_authed: false,
         }
     }
@@ -113,27 +152,45 @@ impl Serialize for FulledPost {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("SimplePost", 12)?;
+        let mut state = serializer.serialize_struct("SimplePost", 15)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("title", &self.title)?;
         state.serialize_field("subtitle", &self.subtitle)?;
         state.serialize_field("published", &self.published)?;
         state.serialize_field("excerpts", &self.excerpts)?;
+        state.serialize_field("autoExcerpts", &self.auto_excerpts)?;
+        state.serialize_field("wordCount", &self.word_count)?;
+        state.serialize_field("readingTime", &self.reading_time)?;
         state.serialize_field("extra", &self.extra)?;
         state.serialize_field("content", &self.content)?;
         state.serialize_field("commentCount", &self.comment_count)?;
+        if self.is_page == Some(true) {
+            state.serialize_field("parentId", &self.parent_id)?;
+            state.serialize_field("sortOrder", &self.sort_order)?;
+        }
         if self.is_authed {
             state.serialize_field("created", &self.created)?;
             state.serialize_field("modified", &self.modified)?;
             state.serialize_field("route", &self.route)?;
             state.serialize_field("isPage", &self.is_page)?;
             state.serialize_field("status", &self.status)?;
+            state.serialize_field("trashedAt", &self.trashed_at)?;
+            state.serialize_field("draft", &self.draft)?;
         }
         state.end()
     }
 }
 // #endregion
 
+/// Where a post stands in its series, `part` counts from 1.
+#[derive(Serialize)]
+pub struct SeriesNav {
+    pub part: usize,
+    pub total: usize,
+    pub previous: Option<SimplePost>,
+    pub next: Option<SimplePost>,
+}
+
 #[derive(Serialize)]
 pub struct PostWithTaxonomy<T: Serialize> {
     #[serde(flatten)]
@@ -141,6 +198,9 @@ pub struct PostWithTaxonomy<T: Serialize> {
     pub categories: Vec<TaxonomyModel>,
     pub tags: Vec<TaxonomyModel>,
     pub series: Option<TaxonomyModel>,
+    /// only filled in for a single post
+    #[serde(rename = "seriesNav", skip_serializing_if = "Option::is_none")]
+    pub series_nav: Option<SeriesNav>,
 }
 
 impl<T: Serialize> PostWithTaxonomy<T> {
@@ -157,6 +217,7 @@ impl<T: Serialize> PostWithTaxonomy<T> {
             categories,
             tags,
             series: series.pop(),
+            series_nav: None,
         }
     }
 
@@ -164,3 +225,18 @@ impl<T: Serialize> PostWithTaxonomy<T> {
         Self::from_classified(post, taxonomies.classify())
     }
 }
+
+/// Matched words are wrapped in `<mark>`, everything else is HTML-escaped.
+#[derive(Serialize)]
+pub struct Headline {
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Serialize)]
+pub struct SearchHit<T: Serialize> {
+    #[serde(flatten)]
+    pub post: PostWithTaxonomy<T>,
+    pub rank: f32,
+    pub headline: Headline,
+}
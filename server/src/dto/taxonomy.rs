@@ -1,4 +1,10 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::core::response::PaginationData;
 use crate::entity::taxonomy::{Model as TaxonomyModel, TaxonomyType};
+
 pub struct ClassifiedTaxonomy {
     pub categories: Vec<TaxonomyModel>,
     pub tags: Vec<TaxonomyModel>,
@@ -32,3 +38,50 @@ impl ClassifyTaxonomy for Vec<TaxonomyModel> {
         classified
     }
 }
+
+/// A category with the ones below it.
+#[derive(Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub item: TaxonomyModel,
+    pub children: Vec<CategoryNode>,
+}
+
+impl CategoryNode {
+    /// Builds the forest of `items`. A category whose parent is missing from
+    /// `items` becomes a root.
+    pub fn tree(items: Vec<TaxonomyModel>) -> Vec<CategoryNode> {
+        let ids: HashSet<i32> = items.iter().map(|item| item.id).collect();
+        let mut children: HashMap<Option<i32>, Vec<TaxonomyModel>> = HashMap::new();
+        for item in items {
+            let parent = item.parent_id.filter(|id| ids.contains(id));
+            children.entry(parent).or_default().push(item);
+        }
+        Self::grow(&mut children, None)
+    }
+
+    fn grow(
+        children: &mut HashMap<Option<i32>, Vec<TaxonomyModel>>,
+        parent: Option<i32>,
+    ) -> Vec<CategoryNode> {
+        children
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| {
+                let id = item.id;
+                CategoryNode {
+                    item,
+                    children: Self::grow(children, Some(id)),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum CategoryList {
+    Tree(Vec<CategoryNode>),
+    Flat(PaginationData<Vec<TaxonomyModel>>),
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ConnectionTrait};
+use serde::Serialize;
+use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+
+/// A named personal access token. Only the hash of the token is stored, the
+/// `prefix` is kept so the owner can tell tokens apart.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "api_tokens")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(indexed)]
+    pub user_id: i64,
+    pub name: String,
+    #[serde(skip)]
+    #[sea_orm(unique, indexed)]
+    pub token: String,
+    pub prefix: String,
+    pub scopes: serde_json::Value,
+    pub created: DateTime<Utc>,
+    #[sea_orm(nullable)]
+    pub last_used: Option<DateTime<Utc>>,
+    #[sea_orm(nullable)]
+    pub expires: Option<DateTime<Utc>>,
+    #[sea_orm(nullable)]
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_enum_str, Deserialize_enum_str)]
+pub enum TokenScope {
+    #[serde(rename = "posts:read")]
+    PostsRead,
+    #[serde(rename = "posts:write")]
+    PostsWrite,
+    #[serde(rename = "taxonomies:write")]
+    TaxonomiesWrite,
+    #[serde(rename = "comments:moderate")]
+    CommentsModerate,
+    #[serde(rename = "options:write")]
+    OptionsWrite,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn is_active(&self) -> bool {
+        self.revoked.is_none() && self.expires.is_none_or(|exp| exp > Utc::now())
+    }
+
+    pub fn scopes(&self) -> Vec<TokenScope> {
+        serde_json::from_value(self.scopes.clone()).unwrap_or_default()
+    }
+}
+
+pub async fn find_by_hash(db: &impl ConnectionTrait, hash: &str) -> Result<Option<Model>, DbErr> {
+    Entity::find()
+        .filter(Column::Token.eq(hash.to_owned()))
+        .one(db)
+        .await
+}
+
+pub async fn touch(db: &impl ConnectionTrait, id: i64) -> Result<(), DbErr> {
+    Entity::update_many()
+        .col_expr(Column::LastUsed, Expr::value(Some(Utc::now())))
+        .filter(Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
-use sea_orm::entity::prelude::*;
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue, ConnectionTrait};
+
 use serde::{Deserialize, Serialize};
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
 
+use super::trash::{self, Trashable};
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
 #[sea_orm(table_name = "comments")]
 #[serde(rename_all = "camelCase")]
@@ -29,6 +32,12 @@ pub struct Model {
     #[sea_orm(ignore)]
     #[serde(skip_deserializing)]
     pub children: Option<Vec<Model>>,
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable, indexed)]
+    pub trashed_at: Option<DateTime<Utc>>,
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable)]
+    pub prior_status: Option<CommentStatus>,
 }
 
 #[derive(
@@ -64,3 +73,42 @@ impl Related<super::post::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+impl Trashable for ActiveModel {
+    type Status = CommentStatus;
+    const TRASHED: CommentStatus = CommentStatus::Trashed;
+    /// pending, so it is moderated again
+    const RESTORED: CommentStatus = CommentStatus::Pending;
+
+    fn status_mut(&mut self) -> &mut ActiveValue<Option<CommentStatus>> {
+        &mut self.status
+    }
+    fn trashed_at_mut(&mut self) -> &mut ActiveValue<Option<DateTime<Utc>>> {
+        &mut self.trashed_at
+    }
+    fn prior_status_mut(&mut self) -> &mut ActiveValue<Option<CommentStatus>> {
+        &mut self.prior_status
+    }
+}
+
+pub async fn restore(db: &impl ConnectionTrait, item: Model) -> Result<Model, DbErr> {
+    let mut am: ActiveModel = item.into();
+    trash::restore(&mut am);
+    am.update(db).await
+}
+
+/// Deletes the comment, its replies move up to take its place so they stay
+/// in the thread.
+pub async fn purge(db: &impl ConnectionTrait, id: i64) -> Result<(), DbErr> {
+    let item = match Entity::find_by_id(id).one(db).await? {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    Entity::update_many()
+        .col_expr(Column::Parent, Expr::value(item.parent))
+        .filter(Column::Parent.eq(id))
+        .exec(db)
+        .await?;
+    Entity::delete_by_id(id).exec(db).await?;
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveEnum, ConnectionTrait, Statement};
+use serde::Serialize;
+use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+
+/// Failed login attempts of one client address or one account.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "login_throttles")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(indexed)]
+    pub kind: ThrottleKind,
+    #[sea_orm(indexed)]
+    pub subject: String,
+    pub failures: i32,
+    pub last_failed: DateTime<Utc>,
+    #[sea_orm(nullable)]
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+#[derive(
+    Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize_enum_str, Deserialize_enum_str,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "THROTTLE_KIND")]
+#[serde(rename_all = "camelCase")]
+pub enum ThrottleKind {
+    #[sea_orm(string_value = "ip")]
+    Ip,
+    #[sea_orm(string_value = "account")]
+    Account,
+}
+
+impl ThrottleKind {
+    /// failures allowed before the first lockout; addresses get more room as
+    /// several people may share one
+    fn free_attempts(&self) -> i32 {
+        match self {
+            ThrottleKind::Ip => 20,
+            ThrottleKind::Account => 5,
+        }
+    }
+}
+
+/// the first lockout, doubled for every further failure
+const BASE_LOCKOUT: i64 = 30;
+const MAX_LOCKOUT: i64 = 60 * 60;
+/// failures older than this are forgotten
+const RESET_AFTER: i64 = 60 * 60 * 24;
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Seconds until the lockout ends, `None` when not locked.
+    pub fn retry_after(&self) -> Option<i64> {
+        self.locked_until
+            .map(|until| (until - Utc::now()).num_seconds())
+            .filter(|secs| *secs > 0)
+    }
+}
+
+async fn find(
+    db: &impl ConnectionTrait,
+    kind: ThrottleKind,
+    subject: &str,
+) -> Result<Option<Model>, DbErr> {
+    Entity::find()
+        .filter(Column::Kind.eq(kind))
+        .filter(Column::Subject.eq(subject.to_owned()))
+        .one(db)
+        .await
+}
+
+pub async fn retry_after(
+    db: &impl ConnectionTrait,
+    kind: ThrottleKind,
+    subject: &str,
+) -> Result<Option<i64>, DbErr> {
+    Ok(find(db, kind, subject)
+        .await?
+        .and_then(|item| item.retry_after()))
+}
+
+/// `record_failure` upserts on `(kind, subject)`, which needs a unique index.
+pub async fn ensure_index(db: &impl ConnectionTrait) -> Result<(), DbErr> {
+    let statements = [
+        // rows recorded before the index existed may be duplicated
+        r#"DELETE FROM "login_throttles" a USING "login_throttles" b
+            WHERE a."kind" = b."kind" AND a."subject" = b."subject" AND a."id" < b."id""#,
+        r#"CREATE UNIQUE INDEX IF NOT EXISTS "login_throttles_kind_subject_idx"
+            ON "login_throttles" ("kind", "subject")"#,
+    ];
+    for sql in statements {
+        db.execute(Statement::from_string(
+            db.get_database_backend(),
+            sql.to_owned(),
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn record_failure(
+    db: &impl ConnectionTrait,
+    kind: ThrottleKind,
+    subject: &str,
+) -> Result<(), DbErr> {
+    let now = Utc::now();
+    // counted in a single statement, concurrent failures all add up
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            r#"INSERT INTO "login_throttles" ("kind", "subject", "failures", "last_failed")
+                VALUES (CAST($1 AS THROTTLE_KIND), $2, 1, $3)
+                ON CONFLICT ("kind", "subject") DO UPDATE SET
+                    "failures" = CASE WHEN "login_throttles"."last_failed" < $4 THEN 1
+                        ELSE "login_throttles"."failures" + 1 END,
+                    "last_failed" = EXCLUDED."last_failed"
+                RETURNING "id", "failures""#,
+            vec![
+                kind.to_value().into(),
+                subject.into(),
+                now.into(),
+                (now - Duration::seconds(RESET_AFTER)).into(),
+            ],
+        ))
+        .await?
+        .ok_or_else(|| DbErr::RecordNotFound("login_throttles".to_owned()))?;
+    let id: i64 = row.try_get("", "id")?;
+    let failures: i32 = row.try_get("", "failures")?;
+    let over = failures - kind.free_attempts();
+    let locked_until = if over > 0 {
+        let secs = BASE_LOCKOUT
+            .saturating_mul(1 << (over - 1).min(20))
+            .min(MAX_LOCKOUT);
+        Some(now + Duration::seconds(secs))
+    } else {
+        None
+    };
+    Entity::update_many()
+        .col_expr(Column::LockedUntil, Expr::value(locked_until))
+        .filter(Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn clear(
+    db: &impl ConnectionTrait,
+    kind: ThrottleKind,
+    subject: &str,
+) -> Result<(), DbErr> {
+    Entity::delete_many()
+        .filter(Column::Kind.eq(kind))
+        .filter(Column::Subject.eq(subject.to_owned()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
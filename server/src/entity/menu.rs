@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// A named navigation menu, such as `main` or `footer`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "menus")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(unique, indexed)]
+    pub name: String,
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::menu_item::Entity")]
+    MenuItem,
+}
+
+impl Related<super::menu_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MenuItem.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,67 @@
+use sea_orm::{entity::prelude::*, ConnectionTrait};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "menu_items")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(indexed)]
+    pub menu_id: i64,
+    pub position: i32,
+    pub kind: MenuItemKind,
+    /// the page or taxonomy pointed at
+    #[sea_orm(nullable)]
+    pub target_id: Option<i64>,
+    /// the address of an `Url` item
+    #[sea_orm(nullable)]
+    pub url: Option<String>,
+    /// shown instead of the title of the page or the name of the taxonomy
+    #[sea_orm(nullable)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "camelCase")]
+pub enum MenuItemKind {
+    #[sea_orm(string_value = "page")]
+    Page,
+    #[sea_orm(string_value = "taxonomy")]
+    Taxonomy,
+    #[sea_orm(string_value = "url")]
+    Url,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::menu::Entity",
+        from = "Column::MenuId",
+        to = "super::menu::Column::Id"
+    )]
+    Menu,
+}
+
+impl Related<super::menu::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Menu.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// Drops the items pointing at a page or taxonomy which is going away.
+pub async fn detach(
+    db: &impl ConnectionTrait,
+    kind: MenuItemKind,
+    target_id: i64,
+) -> Result<(), DbErr> {
+    Entity::delete_many()
+        .filter(Column::Kind.eq(kind))
+        .filter(Column::TargetId.eq(target_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
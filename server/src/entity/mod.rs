@@ -1,11 +1,23 @@
 use sea_orm::{DeriveActiveEnum, EnumIter};
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
 
+pub mod api_token;
 pub mod comment;
+pub mod login_throttle;
+pub mod menu;
+pub mod menu_item;
+pub mod oidc_state;
 pub mod post;
+pub mod post_draft;
+pub mod post_revision;
 pub mod post_taxonomy;
+pub mod preview_token;
+pub mod route_history;
+pub mod session;
 pub mod site_option;
 pub mod taxonomy;
+pub mod trash;
+pub mod user;
 
 #[derive(
     Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize_enum_str, Deserialize_enum_str,
@@ -17,6 +29,12 @@ pub enum UserRole {
     Visitor,
     #[sea_orm(string_value = "manager")]
     Manager,
+    #[sea_orm(string_value = "author")]
+    Author,
+    #[sea_orm(string_value = "editor")]
+    Editor,
+    #[sea_orm(string_value = "admin")]
+    Admin,
 }
 
 impl Default for UserRole {
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+
+/// A pending OpenID Connect authorization, keyed by the `state` parameter.
+/// `browser` holds the hash of the cookie set on the browser which started it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "oidc_states")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub browser: String,
+    /// set when a signed in user is linking the identity to their account
+    #[sea_orm(nullable)]
+    pub user_id: Option<i64>,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
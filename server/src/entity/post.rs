@@ -1,9 +1,18 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
-use sea_orm::{entity::prelude::*, ConnectionTrait, IntoActiveValue};
+use sea_orm::{
+    entity::prelude::*, sea_query::Expr, ActiveValue, Condition, ConnectionTrait, IntoActiveValue,
+};
 use serde::Deserialize;
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
 
-use super::{comment, taxonomy};
+use super::{
+    comment,
+    menu_item::{self, MenuItemKind},
+    post_draft, post_revision, post_taxonomy, preview_token, route_history, taxonomy,
+    trash::{self, Trashable},
+};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize)]
 #[sea_orm(table_name = "posts")]
@@ -27,9 +36,45 @@ pub struct Model {
     pub route: Option<String>,
     #[sea_orm(default_value = false)]
     pub is_page: Option<bool>,
+    /// the page this page sits below, pages only
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable, indexed)]
+    pub parent_id: Option<i64>,
+    /// orders the pages sharing a parent, lowest first
+    #[serde(skip_deserializing)]
+    #[sea_orm(default_value = 0)]
+    pub sort_order: i32,
     pub status: Option<PostStatus>,
     #[sea_orm(nullable)]
     pub extra: Option<serde_json::Value>,
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable, indexed)]
+    pub author_id: Option<i64>,
+    /// derived from `content` on save, see `content::stats`
+    #[serde(skip_deserializing)]
+    #[sea_orm(default_value = 0)]
+    pub word_count: i32,
+    /// in minutes
+    #[serde(skip_deserializing)]
+    #[sea_orm(default_value = 0)]
+    pub reading_time: i32,
+    /// generated from `content` while the author left `excerpts` empty
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable)]
+    pub auto_excerpts: Option<String>,
+    /// plain text of `content`, what search indexes
+    #[serde(skip)]
+    #[sea_orm(nullable)]
+    pub content_text: Option<String>,
+    /// when the post went to the trash, it is purged once this is older than
+    /// the retention period
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable, indexed)]
+    pub trashed_at: Option<DateTime<Utc>>,
+    /// the status to go back to when restored from the trash
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable)]
+    pub prior_status: Option<PostStatus>,
     #[sea_orm(ignore)]
     #[serde(skip)]
     pub comment_count: usize,
@@ -45,6 +90,9 @@ pub enum PostStatus {
     Draft,
     #[sea_orm(string_value = "published")]
     Published,
+    /// published by `task::scheduler` once `published` is due
+    #[sea_orm(string_value = "scheduled")]
+    Scheduled,
     #[sea_orm(string_value = "hidden")]
     Hidden,
     #[sea_orm(string_value = "trashed")]
@@ -67,6 +115,12 @@ impl Default for PostStatus {
 pub enum Relation {
     #[sea_orm(has_many = "super::comment::Entity")]
     Comment,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::AuthorId",
+        to = "super::user::Column::Id"
+    )]
+    User,
 }
 
 impl Related<super::taxonomy::Entity> for Entity {
@@ -84,9 +138,160 @@ impl Related<super::comment::Entity> for Entity {
     }
 }
 
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
 
+impl Trashable for ActiveModel {
+    type Status = PostStatus;
+    const TRASHED: PostStatus = PostStatus::Trashed;
+    const RESTORED: PostStatus = PostStatus::Draft;
+
+    fn status_mut(&mut self) -> &mut ActiveValue<Option<PostStatus>> {
+        &mut self.status
+    }
+    fn trashed_at_mut(&mut self) -> &mut ActiveValue<Option<DateTime<Utc>>> {
+        &mut self.trashed_at
+    }
+    fn prior_status_mut(&mut self) -> &mut ActiveValue<Option<PostStatus>> {
+        &mut self.prior_status
+    }
+}
+
+pub async fn restore(db: &impl ConnectionTrait, item: Model) -> Result<Model, DbErr> {
+    let mut am: ActiveModel = item.into();
+    trash::restore(&mut am);
+    am.modified = ActiveValue::Set(Some(Utc::now()));
+    am.update(db).await
+}
+
+/// Deletes the post along with its comments, taxonomy links, revisions, old
+/// routes and the menu items pointing at it.
+pub async fn purge(db: &impl ConnectionTrait, id: i64) -> Result<(), DbErr> {
+    comment::Entity::delete_many()
+        .filter(comment::Column::PostId.eq(id))
+        .exec(db)
+        .await?;
+    post_taxonomy::Entity::delete_many()
+        .filter(post_taxonomy::Column::PostId.eq(id))
+        .exec(db)
+        .await?;
+    post_revision::Entity::delete_many()
+        .filter(post_revision::Column::PostId.eq(id))
+        .exec(db)
+        .await?;
+    post_draft::Entity::delete_by_id(id).exec(db).await?;
+    // the subpages move up to take its place
+    if let Some(item) = Entity::find_by_id(id).one(db).await? {
+        Entity::update_many()
+            .col_expr(Column::ParentId, Expr::value(item.parent_id))
+            .filter(Column::ParentId.eq(id))
+            .exec(db)
+            .await?;
+    }
+    preview_token::Entity::delete_many()
+        .filter(preview_token::Column::PostId.eq(id))
+        .exec(db)
+        .await?;
+    route_history::Entity::delete_many()
+        .filter(route_history::Column::PostId.eq(id))
+        .exec(db)
+        .await?;
+    menu_item::detach(db, MenuItemKind::Page, id).await?;
+    Entity::delete_by_id(id).exec(db).await?;
+    Ok(())
+}
+
+/// The pages above the pages of `ids`, up to the root pages, in one query.
+pub async fn ancestors(
+    db: &impl ConnectionTrait,
+    ids: &[i64],
+) -> Result<HashMap<i64, Model>, DbErr> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    // the ids are numbers, they can be written into the query as they are;
+    // `UNION` ends the walk on a broken tree with a loop
+    let ids: Vec<String> = ids.iter().map(i64::to_string).collect();
+    let cond = Expr::cust(&format!(
+        r#""posts"."id" IN (
+            WITH RECURSIVE "tree" ("id") AS (
+                SELECT "parent_id" FROM "posts" WHERE "id" IN ({})
+                UNION
+                SELECT "posts"."parent_id" FROM "posts" JOIN "tree" ON "posts"."id" = "tree"."id"
+            )
+            SELECT "id" FROM "tree"
+        )"#,
+        ids.join(", ")
+    ));
+    Ok(Entity::find()
+        .filter(cond)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|item| (item.id, item))
+        .collect())
+}
+
+/// The routes from the root page down to `item`, joined by `/`, with the
+/// pages above it taken from `ancestors`. `None` when a page on the way has
+/// no route, or is hidden and `is_authed` is not set.
+pub fn page_path(item: &Model, ancestors: &HashMap<i64, Model>, is_authed: bool) -> Option<String> {
+    let mut routes = vec![];
+    let mut seen = vec![item.id];
+    let mut current = item;
+    loop {
+        if !is_authed && !current.is_public() {
+            return None;
+        }
+        routes.push(current.route.clone()?);
+        current = match current.parent_id.filter(|id| !seen.contains(id)) {
+            Some(id) => match ancestors.get(&id) {
+                Some(v) => v,
+                None => break,
+            },
+            None => break,
+        };
+        seen.push(current.id);
+    }
+    routes.reverse();
+    Some(routes.join("/"))
+}
+
+/// The posts anyone may read, see `Model::is_public`.
+pub fn public_condition() -> Condition {
+    Condition::all()
+        .add(Column::Status.eq(PostStatus::Published))
+        .add(Column::Published.lte(Utc::now()))
+}
+
+pub async fn touch(db: &impl ConnectionTrait, id: i64) -> Result<(), DbErr> {
+    Entity::update_many()
+        .col_expr(Column::Modified, Expr::value(Utc::now()))
+        .filter(Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
 impl Model {
+    /// Changes with every write as all of them bump `modified`. Saving the
+    /// draft does not write the post, the draft carries its own `modified`.
+    pub fn etag(&self) -> String {
+        let modified = self.modified.map_or(0, |v| v.timestamp_micros());
+        format!("\"{}-{}\"", self.id, modified)
+    }
+
+    /// Whether anyone may read the post.
+    pub fn is_public(&self) -> bool {
+        self.status == Some(PostStatus::Published)
+            && self.published.is_some_and(|v| v <= Utc::now())
+    }
+
     pub async fn txs(&self, db: &impl ConnectionTrait) -> Result<Vec<taxonomy::Model>, DbErr> {
         Ok(self.find_related(taxonomy::Entity).all(db).await?)
     }
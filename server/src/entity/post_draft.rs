@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue, ConnectionTrait};
+use serde::{Deserialize, Serialize};
+
+use super::post;
+
+/// The autosave slot of a post. The editor keeps writing here, readers keep
+/// seeing the post row until the draft is published.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "post_drafts")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[serde(skip_deserializing)]
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub post_id: i64,
+    /// may be left out like the rest, a fresh draft takes it from the post
+    #[serde(default)]
+    pub title: String,
+    #[sea_orm(nullable)]
+    pub subtitle: Option<String>,
+    #[sea_orm(nullable)]
+    pub excerpts: Option<String>,
+    #[sea_orm(nullable)]
+    pub content: Option<serde_json::Value>,
+    #[sea_orm(nullable)]
+    pub extra: Option<serde_json::Value>,
+    /// who saved the draft last
+    #[serde(skip_deserializing)]
+    #[sea_orm(nullable)]
+    pub editor_id: Option<i64>,
+    #[serde(skip_deserializing)]
+    pub modified: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::post::Entity",
+        from = "Column::PostId",
+        to = "super::post::Column::Id"
+    )]
+    Post,
+}
+
+impl Related<super::post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Post.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// Writes the fields set in `am` to the draft of `item`. A fresh draft takes
+/// the fields left out from the post.
+pub async fn save(
+    db: &impl ConnectionTrait,
+    item: &post::Model,
+    mut am: ActiveModel,
+    editor_id: i64,
+) -> Result<Model, DbErr> {
+    let exists = Entity::find_by_id(item.id).one(db).await?.is_some();
+    am.post_id = ActiveValue::Set(item.id);
+    am.editor_id = ActiveValue::Set(Some(editor_id));
+    am.modified = ActiveValue::Set(Utc::now());
+    if exists {
+        return am.update(db).await;
+    }
+    if am.title.is_not_set() {
+        am.title = ActiveValue::Set(item.title.clone());
+    }
+    if am.subtitle.is_not_set() {
+        am.subtitle = ActiveValue::Set(item.subtitle.clone());
+    }
+    if am.excerpts.is_not_set() {
+        am.excerpts = ActiveValue::Set(item.excerpts.clone());
+    }
+    if am.content.is_not_set() {
+        am.content = ActiveValue::Set(item.content.clone());
+    }
+    if am.extra.is_not_set() {
+        am.extra = ActiveValue::Set(item.extra.clone());
+    }
+    am.insert(db).await
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue, ConnectionTrait};
+use serde::Serialize;
+
+use super::post;
+
+/// A snapshot of a post's editable fields, taken before each update.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "post_revisions")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(indexed)]
+    pub post_id: i64,
+    pub title: String,
+    #[sea_orm(nullable)]
+    pub subtitle: Option<String>,
+    #[sea_orm(nullable)]
+    pub excerpts: Option<String>,
+    #[sea_orm(nullable)]
+    pub content: Option<serde_json::Value>,
+    #[sea_orm(nullable)]
+    pub extra: Option<serde_json::Value>,
+    /// who made the change that replaced this version
+    #[sea_orm(nullable)]
+    pub editor_id: Option<i64>,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::post::Entity",
+        from = "Column::PostId",
+        to = "super::post::Column::Id"
+    )]
+    Post,
+}
+
+impl Related<super::post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Post.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// The revisioned fields as one document, the input of `utils::json_diff`.
+    pub fn document(&self) -> serde_json::Value {
+        serde_json::json!({
+            "title": self.title,
+            "subtitle": self.subtitle,
+            "excerpts": self.excerpts,
+            "content": self.content,
+            "extra": self.extra,
+        })
+    }
+}
+
+pub fn document_of(item: &post::Model) -> serde_json::Value {
+    serde_json::json!({
+        "title": item.title,
+        "subtitle": item.subtitle,
+        "excerpts": item.excerpts,
+        "content": item.content,
+        "extra": item.extra,
+    })
+}
+
+pub async fn snapshot(
+    db: &impl ConnectionTrait,
+    item: &post::Model,
+    editor_id: Option<i64>,
+) -> Result<Model, DbErr> {
+    ActiveModel {
+        post_id: ActiveValue::Set(item.id),
+        title: ActiveValue::Set(item.title.clone()),
+        subtitle: ActiveValue::Set(item.subtitle.clone()),
+        excerpts: ActiveValue::Set(item.excerpts.clone()),
+        content: ActiveValue::Set(item.content.clone()),
+        extra: ActiveValue::Set(item.extra.clone()),
+        editor_id: ActiveValue::Set(editor_id),
+        created: ActiveValue::Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
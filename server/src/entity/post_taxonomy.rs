@@ -1,9 +1,18 @@
-use super::taxonomy;
+use std::collections::HashMap;
+
+use super::{post, taxonomy};
+use chrono::Utc;
 use sea_orm::prelude::*;
+use sea_orm::sea_query::{Expr, SimpleExpr};
+use sea_orm::ActiveValue;
 use sea_orm::ColumnTrait;
+use sea_orm::Condition;
 use sea_orm::ConnectionTrait;
 use sea_orm::DbErr;
 use sea_orm::IntoActiveModel;
+use sea_orm::JoinType;
+use sea_orm::QueryOrder;
+use sea_orm::QuerySelect;
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "post_taxonomy")]
 pub struct Model {
@@ -12,6 +21,9 @@ pub struct Model {
     pub post_id: i64,
     pub taxonomy_id: i32,
     pub taxonomy_type: taxonomy::TaxonomyType,
+    /// the reading order within a series, unused by other types
+    #[sea_orm(default_value = 0)]
+    pub position: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -61,6 +73,15 @@ pub async fn update(
     if !is_valid {
         return Err(DbErr::Custom("invalid taxonomy".to_owned()));
     }
+    // a post staying in a series keeps its place there
+    let positions: HashMap<i32, i32> = Entity::find()
+        .filter(Column::PostId.eq(pid))
+        .filter(Column::TaxonomyType.eq(t_type.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|item| (item.taxonomy_id, item.position))
+        .collect();
     Entity::delete_many()
         .filter(
             Column::TaxonomyType
@@ -69,13 +90,127 @@ pub async fn update(
         )
         .exec(db)
         .await?;
-    let items: Vec<ActiveModel> = tids
-        .clone()
+    let mut items: Vec<ActiveModel> = Vec::with_capacity(tids.len());
+    for tid in tids {
+        let mut am = UpdatePayload::new(pid, tid, t_type.clone()).into_active_model();
+        am.position = ActiveValue::Set(match positions.get(&tid) {
+            Some(position) => *position,
+            None => next_position(db, tid, &t_type).await?,
+        });
+        items.push(am);
+    }
+    Entity::insert_many(items).exec(db).await?;
+    Ok(())
+}
+
+/// New posts go to the end of a series.
+async fn next_position(
+    db: &impl ConnectionTrait,
+    tid: i32,
+    t_type: &taxonomy::TaxonomyType,
+) -> Result<i32, DbErr> {
+    if *t_type != taxonomy::TaxonomyType::Series {
+        return Ok(0);
+    }
+    let last = Entity::find()
+        .filter(Column::TaxonomyId.eq(tid))
+        .order_by_desc(Column::Position)
+        .one(db)
+        .await?;
+    Ok(last.map_or(1, |item| item.position + 1))
+}
+
+/// Links the post to those of `tids` it is not linked to yet.
+pub async fn attach(
+    db: &impl ConnectionTrait,
+    pid: i64,
+    tids: &[i32],
+    t_type: taxonomy::TaxonomyType,
+) -> Result<(), DbErr> {
+    let linked: Vec<i32> = Entity::find()
+        .filter(Column::PostId.eq(pid))
+        .filter(Column::TaxonomyId.is_in(tids.to_vec()))
+        .all(db)
+        .await?
         .into_iter()
-        .map(|tid| UpdatePayload::new(pid, tid, t_type.clone()).into_active_model())
+        .map(|item| item.taxonomy_id)
         .collect();
+    let mut items: Vec<ActiveModel> = Vec::with_capacity(tids.len());
+    for tid in tids.iter().filter(|tid| !linked.contains(tid)) {
+        let mut am = UpdatePayload::new(pid, *tid, t_type.clone()).into_active_model();
+        am.position = ActiveValue::Set(next_position(db, *tid, &t_type).await?);
+        items.push(am);
+    }
+    if items.is_empty() {
+        return Ok(());
+    }
     Entity::insert_many(items).exec(db).await?;
     Ok(())
 }
 
+pub async fn detach(db: &impl ConnectionTrait, pid: i64, tids: &[i32]) -> Result<(), DbErr> {
+    Entity::delete_many()
+        .filter(Column::PostId.eq(pid))
+        .filter(Column::TaxonomyId.is_in(tids.to_vec()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Posts of the series matching `cond`, in reading order. Posts never
+/// reordered fall back to the order they were published in.
+pub async fn series_posts(
+    db: &impl ConnectionTrait,
+    series_id: i32,
+    cond: Condition,
+) -> Result<Vec<post::Model>, DbErr> {
+    post::Entity::find()
+        .join(JoinType::InnerJoin, Relation::Post.def().rev())
+        .filter(
+            Condition::all()
+                .add(Column::TaxonomyId.eq(series_id))
+                .add(cond),
+        )
+        .order_by_asc(Column::Position)
+        .order_by_asc(post::Column::Published)
+        .order_by_asc(post::Column::Id)
+        .all(db)
+        .await
+}
+
+/// Matches the posts filed under the category or any category below it.
+pub fn in_category_tree(category_id: i32) -> SimpleExpr {
+    // `UNION` rather than `UNION ALL`, a broken tree with a loop still ends
+    Expr::cust_with_values(
+        r#""posts"."id" IN (SELECT "post_id" FROM "post_taxonomy" WHERE "taxonomy_id" IN (
+            WITH RECURSIVE "tree" ("id") AS (
+                SELECT "id" FROM "taxonomy" WHERE "id" = $1 AND "type" = 'category'
+                UNION
+                SELECT "taxonomy"."id" FROM "taxonomy" JOIN "tree" ON "taxonomy"."parent_id" = "tree"."id"
+            )
+            SELECT "id" FROM "tree"
+        ))"#,
+        vec![category_id],
+    )
+}
+
+/// Numbers the posts of the series in the order of `pids`. Their `seriesNav`
+/// changes with it, so they are touched for new ETags.
+pub async fn reorder(db: &impl ConnectionTrait, series_id: i32, pids: &[i64]) -> Result<(), DbErr> {
+    for (index, pid) in pids.iter().enumerate() {
+        Entity::update_many()
+            .col_expr(Column::Position, Expr::value(index as i32 + 1))
+            .filter(Column::TaxonomyId.eq(series_id))
+            .filter(Column::PostId.eq(*pid))
+            .exec(db)
+            .await?;
+    }
+    post::Entity::update_many()
+        .col_expr(post::Column::Modified, Expr::value(Utc::now()))
+        .filter(post::Column::Id.is_in(pids.to_vec()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, ConnectionTrait};
+use serde::Serialize;
+
+/// Lets anyone holding the link read one unpublished post. Only the hash of
+/// the token is stored.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "preview_tokens")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(indexed)]
+    pub post_id: i64,
+    #[serde(skip)]
+    #[sea_orm(unique, indexed)]
+    pub token: String,
+    pub created_by: i64,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+    #[sea_orm(nullable)]
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::post::Entity",
+        from = "Column::PostId",
+        to = "super::post::Column::Id"
+    )]
+    Post,
+}
+
+impl Related<super::post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Post.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn is_expired(&self) -> bool {
+        self.expires <= Utc::now()
+    }
+}
+
+pub async fn find_by_hash(
+    db: &impl ConnectionTrait,
+    post_id: i64,
+    hash: &str,
+) -> Result<Option<Model>, DbErr> {
+    Entity::find()
+        .filter(Column::PostId.eq(post_id))
+        .filter(Column::Token.eq(hash.to_owned()))
+        .one(db)
+        .await
+}
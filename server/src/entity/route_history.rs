@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue, ConnectionTrait};
+use serde::Serialize;
+
+/// A route a post was reachable at before, kept so old links can redirect.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "route_history")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(unique, indexed)]
+    pub route: String,
+    #[sea_orm(indexed)]
+    pub post_id: i64,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::post::Entity",
+        from = "Column::PostId",
+        to = "super::post::Column::Id"
+    )]
+    Post,
+}
+
+impl Related<super::post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Post.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub async fn find_by_route(db: &impl ConnectionTrait, route: &str) -> Result<Option<Model>, DbErr> {
+    Entity::find().filter(Column::Route.eq(route)).one(db).await
+}
+
+/// Remembers that `old` led to the post now at `current`.
+pub async fn record(
+    db: &impl ConnectionTrait,
+    post_id: i64,
+    old: &str,
+    current: Option<&str>,
+) -> Result<(), DbErr> {
+    // the current route is served directly, it must not redirect anymore
+    if let Some(current) = current {
+        Entity::delete_many()
+            .filter(Column::Route.eq(current))
+            .exec(db)
+            .await?;
+    }
+    match find_by_route(db, old).await? {
+        Some(item) => {
+            let mut am: ActiveModel = item.into();
+            am.post_id = ActiveValue::Set(post_id);
+            am.created = ActiveValue::Set(Utc::now());
+            am.update(db).await?;
+        }
+        None => {
+            ActiveModel {
+                route: ActiveValue::Set(old.to_owned()),
+                post_id: ActiveValue::Set(post_id),
+                created: ActiveValue::Set(Utc::now()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ConnectionTrait};
+
+/// A login session. Its id is the `jti` of every access token issued for it,
+/// and `refresh_token` holds the hash of the current (rotating) refresh token.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "sessions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    #[sea_orm(indexed)]
+    pub user_id: i64,
+    pub refresh_token: String,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+    #[sea_orm(nullable)]
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn is_active(&self) -> bool {
+        self.revoked.is_none() && self.expires > Utc::now()
+    }
+}
+
+pub async fn is_active(db: &impl ConnectionTrait, id: &str) -> Result<bool, DbErr> {
+    Ok(Entity::find_by_id(id.to_owned())
+        .one(db)
+        .await?
+        .is_some_and(|session| session.is_active()))
+}
+
+pub async fn revoke(db: &impl ConnectionTrait, id: &str) -> Result<(), DbErr> {
+    Entity::update_many()
+        .col_expr(Column::Revoked, Expr::value(Some(Utc::now())))
+        .filter(Column::Id.eq(id.to_owned()))
+        .filter(Column::Revoked.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn revoke_all(db: &impl ConnectionTrait, user_id: i64) -> Result<u64, DbErr> {
+    let res = Entity::update_many()
+        .col_expr(Column::Revoked, Expr::value(Some(Utc::now())))
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::Revoked.is_null())
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+/// Swaps the refresh token hash only while it still is `current`, returning
+/// whether it did. Of two refreshes racing with the same token one wins.
+pub async fn rotate(
+    db: &impl ConnectionTrait,
+    id: &str,
+    current: &str,
+    refresh_token: String,
+) -> Result<bool, DbErr> {
+    let res = Entity::update_many()
+        .col_expr(Column::RefreshToken, Expr::value(refresh_token))
+        .filter(Column::Id.eq(id.to_owned()))
+        .filter(Column::RefreshToken.eq(current.to_owned()))
+        .filter(Column::Revoked.is_null())
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected == 1)
+}
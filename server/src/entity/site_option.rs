@@ -2,7 +2,7 @@ use serde::Deserialize;
 use serde_enum_str::Deserialize_enum_str;
 use std::collections::HashMap;
 
-use sea_orm::entity::prelude::*;
+use sea_orm::{entity::prelude::*, ConnectionTrait};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -42,6 +42,14 @@ impl Model {
     }
 }
 
+pub async fn get_value(db: &impl ConnectionTrait, name: &str) -> Result<Option<String>, DbErr> {
+    Ok(Entity::find()
+        .filter(Column::Name.eq(name))
+        .one(db)
+        .await?
+        .map(|opt| opt.value))
+}
+
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
@@ -17,10 +17,18 @@ pub struct Model {
     pub t_type: TaxonomyType,
     #[sea_orm(nullable)]
     pub extra: Option<serde_json::Value>,
+    /// only categories nest, and never below themselves
+    #[sea_orm(nullable, indexed)]
+    pub parent_id: Option<i32>,
+    /// bumped on every update, the source of the `ETag`
+    #[serde(skip_deserializing)]
+    #[sea_orm(default_value = 0)]
+    pub version: i32,
 }
 
-#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum)]
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "TAXONOMY_TYPE")]
+#[serde(rename_all = "camelCase")]
 pub enum TaxonomyType {
     #[sea_orm(string_value = "category")]
     Category,
@@ -47,6 +55,12 @@ pub enum Relation {}
 
 impl ActiveModelBehavior for ActiveModel {}
 
+impl Model {
+    pub fn etag(&self) -> String {
+        format!("\"t{}-{}\"", self.id, self.version)
+    }
+}
+
 impl Related<super::post::Entity> for Entity {
     fn to() -> RelationDef {
         super::post_taxonomy::Relation::Post.def()
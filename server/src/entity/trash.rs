@@ -0,0 +1,45 @@
+//! The trash shared by posts and comments. Trashing an item remembers when
+//! and the status it had, restoring it goes back to that status.
+
+use chrono::{DateTime, Utc};
+use sea_orm::{sea_query::Nullable, ActiveValue, Value};
+
+pub trait Trashable {
+    type Status: Clone + PartialEq + Into<Value> + Nullable;
+    /// the status of items in the trash
+    const TRASHED: Self::Status;
+    /// what an item is restored to when its prior status is unknown
+    const RESTORED: Self::Status;
+
+    fn status_mut(&mut self) -> &mut ActiveValue<Option<Self::Status>>;
+    fn trashed_at_mut(&mut self) -> &mut ActiveValue<Option<DateTime<Utc>>>;
+    fn prior_status_mut(&mut self) -> &mut ActiveValue<Option<Self::Status>>;
+}
+
+/// Keeps `trashed_at` and `prior_status` in step when `am` changes the status
+/// from `before`.
+pub fn track<A: Trashable>(am: &mut A, before: &Option<A::Status>) {
+    let status = match am.status_mut() {
+        ActiveValue::Set(v) => v.clone(),
+        _ => return,
+    };
+    let trashed = Some(A::TRASHED);
+    if status == trashed && *before != trashed {
+        *am.trashed_at_mut() = ActiveValue::Set(Some(Utc::now()));
+        *am.prior_status_mut() = ActiveValue::Set(before.clone());
+    } else if status != trashed && *before == trashed {
+        *am.trashed_at_mut() = ActiveValue::Set(None);
+        *am.prior_status_mut() = ActiveValue::Set(None);
+    }
+}
+
+/// Sets the status back to the one before trashing, `A::RESTORED` if unknown.
+pub fn restore<A: Trashable>(am: &mut A) {
+    let prior = match am.prior_status_mut() {
+        ActiveValue::Set(v) | ActiveValue::Unchanged(v) => v.clone(),
+        ActiveValue::NotSet => None,
+    };
+    *am.status_mut() = ActiveValue::Set(Some(prior.unwrap_or(A::RESTORED)));
+    *am.trashed_at_mut() = ActiveValue::Set(None);
+    *am.prior_status_mut() = ActiveValue::Set(None);
+}
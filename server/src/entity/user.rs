@@ -0,0 +1,158 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use sea_orm::{entity::prelude::*, sea_query::Expr, Condition, ConnectionTrait};
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "users")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(unique, indexed)]
+    pub email: String,
+    pub nickname: String,
+    #[serde(skip)]
+    pub password: String,
+    pub role: super::UserRole,
+    /// base32 TOTP secret, only in effect once `totp_enabled` is set
+    #[serde(skip)]
+    #[sea_orm(nullable)]
+    pub totp_secret: Option<String>,
+    #[sea_orm(default_value = false)]
+    pub totp_enabled: bool,
+    /// the last accepted TOTP time step, codes can not be replayed
+    #[serde(skip)]
+    #[sea_orm(nullable)]
+    pub totp_last_step: Option<i64>,
+    /// hashes of the recovery codes which have not been used yet
+    #[serde(skip)]
+    #[sea_orm(nullable)]
+    pub recovery_codes: Option<serde_json::Value>,
+    /// `iss` and `sub` of the linked account at the OpenID Connect provider
+    #[serde(skip)]
+    #[sea_orm(nullable)]
+    pub oidc_issuer: Option<String>,
+    #[serde(skip)]
+    #[sea_orm(nullable, unique, indexed)]
+    pub oidc_subject: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::post::Entity")]
+    Post,
+}
+
+impl Related<super::post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Post.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+fn verify_hash(hash: &str, password: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+static DUMMY_HASH: Lazy<String> =
+    Lazy::new(|| hash_password("dummy").expect("dummy password hash fail!"));
+
+/// Verifies against a throwaway hash, so an unknown email costs
+/// as much time as a wrong password.
+pub fn verify_dummy_password(password: &str) {
+    std::hint::black_box(verify_hash(&DUMMY_HASH, password));
+}
+
+impl Model {
+    pub fn verify_password(&self, password: &str) -> bool {
+        verify_hash(&self.password, password)
+    }
+}
+
+/// Accepts the TOTP time step only if it is newer than the last one, so
+/// a code can not be used twice even by concurrent requests.
+pub async fn consume_totp_step(
+    db: &impl ConnectionTrait,
+    id: i64,
+    step: i64,
+) -> Result<bool, DbErr> {
+    let res = Entity::update_many()
+        .col_expr(Column::TotpLastStep, Expr::value(step))
+        .filter(Column::Id.eq(id))
+        .filter(
+            Condition::any()
+                .add(Column::TotpLastStep.is_null())
+                .add(Column::TotpLastStep.lt(step)),
+        )
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected == 1)
+}
+
+/// Removes the hashed recovery code, false if it was not (or no longer) stored.
+pub async fn consume_recovery_code(
+    db: &impl ConnectionTrait,
+    id: i64,
+    hashed: &str,
+) -> Result<bool, DbErr> {
+    let res = Entity::update_many()
+        .col_expr(
+            Column::RecoveryCodes,
+            Expr::cust_with_values(r#"("recovery_codes"::jsonb - $1)::json"#, vec![hashed]),
+        )
+        .filter(Column::Id.eq(id))
+        .filter(Expr::cust_with_values(
+            r#"jsonb_exists("recovery_codes"::jsonb, $1)"#,
+            vec![hashed],
+        ))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected == 1)
+}
+
+pub async fn find_by_email(db: &impl ConnectionTrait, email: &str) -> Result<Option<Model>, DbErr> {
+    Entity::find()
+        .filter(Column::Email.eq(email.trim().to_lowercase()))
+        .one(db)
+        .await
+}
+
+pub async fn create(
+    db: &impl ConnectionTrait,
+    email: &str,
+    nickname: &str,
+    password: &str,
+    role: super::UserRole,
+) -> Result<Model, Box<dyn std::error::Error>> {
+    let am = ActiveModel {
+        email: sea_orm::ActiveValue::Set(email.trim().to_lowercase()),
+        nickname: sea_orm::ActiveValue::Set(nickname.to_owned()),
+        password: sea_orm::ActiveValue::Set(hash_password(password)?),
+        role: sea_orm::ActiveValue::Set(role),
+        totp_enabled: sea_orm::ActiveValue::Set(false),
+        created: sea_orm::ActiveValue::Set(Some(Utc::now())),
+        modified: sea_orm::ActiveValue::Set(Some(Utc::now())),
+        ..Default::default()
+    };
+    Ok(am.insert(db).await?)
+}
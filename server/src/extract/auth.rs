@@ -1,36 +1,95 @@
-use crate::{core::error::ErrorCode, e_code, e_code_err};
+use std::sync::Arc;
+
+use crate::{
+    core::{error::ErrorCode, AppState},
+    e_code, e_code_err,
+    entity::{
+        api_token::{self, TokenScope},
+        session, user, UserRole,
+    },
+    utils::sha256_hex,
+};
+
+use super::permission::Permission;
 use axum::{
     async_trait,
     extract::{FromRequest, RequestParts, TypedHeader},
     headers::{authorization::Bearer, Authorization},
 };
-use jsonwebtoken::{decode, errors::ErrorKind, DecodingKey, EncodingKey, Validation};
-use once_cell::sync::Lazy;
+use jsonwebtoken::errors::ErrorKind;
+use sea_orm::EntityTrait;
 use serde::{Deserialize, Serialize};
 
-pub struct Key {
-    pub encoding: EncodingKey,
-    pub decoding: DecodingKey,
-}
-
-impl Key {
-    fn new(secret: &[u8]) -> Self {
-        Self {
-            encoding: EncodingKey::from_secret(secret),
-            decoding: DecodingKey::from_secret(secret),
-        }
-    }
-}
-
-pub static KEY: Lazy<Key> = Lazy::new(|| Key::new(crate::core::APP_CONFIG.jwt.secret.as_bytes()));
+pub use super::keys::KEYS;
 
 use crate::core::error::AppError;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
+    pub sub: i64,
+    pub role: UserRole,
+    /// id of the session the token was issued for
+    pub jti: String,
     pub email: String,
     pub nickname: String,
     pub exp: i64,
+    /// set when the request was authorized by a personal access token
+    #[serde(skip)]
+    pub scopes: Option<Vec<TokenScope>>,
+}
+
+/// Personal access tokens carry this prefix, everything else is a JWT.
+pub const API_TOKEN_PREFIX: &str = "pz_";
+
+impl Claims {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.role.can(permission)
+            && self
+                .scopes
+                .as_ref()
+                .is_none_or(|scopes| scopes.iter().any(|scope| scope.grants(permission)))
+    }
+
+    pub fn is_api_token(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    /// Account and credential endpoints only take interactive sessions, so a
+    /// leaked token can not be used to take the account over.
+    pub fn ensure_session(&self) -> Result<(), AppError> {
+        if self.is_api_token() {
+            return e_code_err!(
+                ErrorCode::Forbidden,
+                Some("api tokens can not be used here.".to_owned())
+            );
+        }
+        Ok(())
+    }
+
+    async fn from_api_token(state: &AppState, token: &str) -> Result<Self, AppError> {
+        let item = match api_token::find_by_hash(&state.db, &sha256_hex(token)).await? {
+            Some(v) if v.is_active() => v,
+            Some(_) => return e_code_err!(ErrorCode::RevokedToken),
+            None => return e_code_err!(ErrorCode::InvalidToken),
+        };
+        let owner = match user::Entity::find_by_id(item.user_id)
+            .one(&state.db)
+            .await?
+        {
+            Some(v) => v,
+            None => return e_code_err!(ErrorCode::InvalidToken),
+        };
+        api_token::touch(&state.db, item.id).await?;
+        Ok(Self {
+            sub: owner.id,
+            role: owner.role,
+            jti: format!("api-token:{}", item.id),
+            email: owner.email,
+            nickname: owner.nickname,
+            exp: item.expires.map_or(i64::MAX, |exp| exp.timestamp()),
+            scopes: Some(item.scopes()),
+        })
+    }
 }
 
 #[async_trait]
@@ -45,14 +104,26 @@ impl<T: Send + Sync> FromRequest<T> for Claims {
             TypedHeader::<Authorization<Bearer>>::from_request(req)
                 .await
                 .map_err(|_| e_code!(ErrorCode::InvalidToken))?;
-        let token_data =
-            decode(bearer.token(), &KEY.decoding, &Validation::default()).map_err(|err| {
+        let state = req
+            .extensions()
+            .get::<Arc<AppState>>()
+            .cloned()
+            .ok_or_else(|| e_code!(ErrorCode::UnkownError))?;
+        if bearer.token().starts_with(API_TOKEN_PREFIX) {
+            return Self::from_api_token(&state, bearer.token()).await;
+        }
+        let token_data = KEYS
+            .decode::<Claims>(bearer.token(), |_| {})
+            .map_err(|err| {
                 let code = match err.kind() {
                     ErrorKind::ExpiredSignature => ErrorCode::ExpiredToken,
                     _ => ErrorCode::InvalidToken,
                 };
                 e_code!(code)
             })?;
+        if !session::is_active(&state.db, &token_data.claims.jti).await? {
+            return e_code_err!(ErrorCode::RevokedToken);
+        }
         Ok(token_data.claims)
     }
 }
@@ -65,6 +136,12 @@ impl WeekClaims {
     pub fn is_authed(&self) -> bool {
         self.claims.is_some()
     }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.claims
+            .as_ref()
+            .is_some_and(|claims| claims.can(permission))
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,41 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequest, RequestParts},
+};
+
+use crate::{
+    core::{
+        error::{AppError, ErrorCode},
+        APP_CONFIG,
+    },
+    e_code,
+};
+
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<T: Send + Sync> FromRequest<T> for ClientIp {
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<T>) -> Result<Self, Self::Rejection> {
+        if APP_CONFIG.server.trust_proxy {
+            // the entries before the last one come from the client, only the
+            // hop appended by our own proxy can be trusted
+            let forwarded = req
+                .headers()
+                .get("X-Forwarded-For")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.rsplit(',').next())
+                .and_then(|v| v.trim().parse::<IpAddr>().ok());
+            if let Some(ip) = forwarded {
+                return Ok(Self(ip));
+            }
+        }
+        let ConnectInfo(addr) = ConnectInfo::<SocketAddr>::from_request(req)
+            .await
+            .map_err(|_| e_code!(ErrorCode::UnkownError))?;
+        Ok(Self(addr.ip()))
+    }
+}
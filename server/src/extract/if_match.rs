@@ -0,0 +1,50 @@
+use axum::{
+    async_trait,
+    extract::{FromRequest, RequestParts},
+    http::{header, HeaderValue},
+};
+
+use crate::{
+    core::error::{AppError, ErrorCode},
+    e_code, e_code_err,
+};
+
+/// The `If-Match` header of a request, `None` when it was not sent.
+pub struct IfMatch(pub Option<String>);
+
+impl IfMatch {
+    /// Lets the request through only if the client saw the current version,
+    /// `etag` is the one of the resource as stored now.
+    pub fn check(&self, etag: &str) -> Result<(), AppError> {
+        let header = match &self.0 {
+            Some(v) => v,
+            None => return e_code_err!(ErrorCode::PreconditionRequired),
+        };
+        if header
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag == etag)
+        {
+            return Ok(());
+        }
+        let err = e_code!(ErrorCode::PreconditionFailed);
+        Err(match HeaderValue::from_str(etag) {
+            Ok(value) => err.with_header(header::ETAG, value),
+            Err(_) => err,
+        })
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync> FromRequest<T> for IfMatch {
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<T>) -> Result<Self, Self::Rejection> {
+        let value = req
+            .headers()
+            .get(header::IF_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_owned);
+        Ok(Self(value))
+    }
+}
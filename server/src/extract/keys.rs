@@ -0,0 +1,202 @@
+//! JWT signing keys. With `HS256` a single shared secret signs and verifies
+//! every token. With `RS256` or `EdDSA` keys are read from `jwt.key_dir`:
+//! `<kid>.pem` holds a private key and `<kid>.pub.pem` its public key. The key
+//! named by `jwt.kid` signs new tokens, every key with a public file verifies,
+//! so a rotated-out key keeps working until its tokens have expired.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use jsonwebtoken::{
+    decode, decode_header, encode, errors::Error as JwtError, errors::ErrorKind, Algorithm,
+    DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
+use once_cell::sync::Lazy;
+use rsa::{pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, PublicKeyParts, RsaPublicKey};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+
+use crate::core::{JWTConfig, APP_CONFIG};
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, the raw key follows it.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+pub struct Key {
+    pub kid: Option<String>,
+    pub algorithm: Algorithm,
+    pub encoding: Option<EncodingKey>,
+    pub decoding: DecodingKey,
+    jwk: Option<serde_json::Value>,
+}
+
+pub struct KeyRing {
+    active: Key,
+    retired: HashMap<String, Key>,
+}
+
+fn base64_url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn public_jwk(kid: &str, algorithm: Algorithm, public_pem: &str) -> Option<serde_json::Value> {
+    match algorithm {
+        Algorithm::RS256 => {
+            let key = RsaPublicKey::from_public_key_pem(public_pem)
+                .or_else(|_| RsaPublicKey::from_pkcs1_pem(public_pem))
+                .ok()?;
+            Some(json!({
+                "kty": "RSA",
+                "use": "sig",
+                "alg": "RS256",
+                "kid": kid,
+                "n": base64_url(&key.n().to_bytes_be()),
+                "e": base64_url(&key.e().to_bytes_be()),
+            }))
+        }
+        Algorithm::EdDSA => {
+            let der = pem::parse(public_pem).ok()?.contents;
+            let raw = der.strip_prefix(&ED25519_SPKI_PREFIX[..])?;
+            Some(json!({
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "use": "sig",
+                "alg": "EdDSA",
+                "kid": kid,
+                "x": base64_url(raw),
+            }))
+        }
+        _ => None,
+    }
+}
+
+impl Key {
+    fn from_secret(secret: &[u8]) -> Self {
+        Self {
+            kid: None,
+            algorithm: Algorithm::HS256,
+            encoding: Some(EncodingKey::from_secret(secret)),
+            decoding: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    fn from_dir(dir: &Path, kid: &str, algorithm: Algorithm) -> Result<Self, String> {
+        let read =
+            |name: String| fs::read(dir.join(&name)).map_err(|err| format!("{}: {}", name, err));
+        let public_pem = read(format!("{}.pub.pem", kid))?;
+        let private_pem = read(format!("{}.pem", kid)).ok();
+        let (encoding, decoding) = match algorithm {
+            Algorithm::RS256 => (
+                private_pem
+                    .map(|pem| EncodingKey::from_rsa_pem(&pem))
+                    .transpose(),
+                DecodingKey::from_rsa_pem(&public_pem),
+            ),
+            Algorithm::EdDSA => (
+                private_pem
+                    .map(|pem| EncodingKey::from_ed_pem(&pem))
+                    .transpose(),
+                DecodingKey::from_ed_pem(&public_pem),
+            ),
+            _ => return Err(format!("{:?} keys can not be loaded from files", algorithm)),
+        };
+        let public_pem = String::from_utf8_lossy(&public_pem);
+        Ok(Self {
+            kid: Some(kid.to_owned()),
+            algorithm,
+            encoding: encoding.map_err(|err| format!("{}.pem: {}", kid, err))?,
+            decoding: decoding.map_err(|err| format!("{}.pub.pem: {}", kid, err))?,
+            jwk: public_jwk(kid, algorithm, &public_pem),
+        })
+    }
+
+    pub fn validation(&self) -> Validation {
+        Validation::new(self.algorithm)
+    }
+}
+
+impl KeyRing {
+    pub fn from_config(conf: &JWTConfig) -> Result<Self, String> {
+        let algorithm = conf
+            .algorithm
+            .parse::<Algorithm>()
+            .map_err(|err| err.to_string())?;
+        if algorithm == Algorithm::HS256 {
+            if conf.secret.is_empty() {
+                return Err("jwt.secret is required for HS256".to_owned());
+            }
+            return Ok(Self {
+                active: Key::from_secret(conf.secret.as_bytes()),
+                retired: HashMap::new(),
+            });
+        }
+        let (dir, kid) = match (&conf.key_dir, &conf.kid) {
+            (Some(dir), Some(kid)) => (Path::new(dir), kid),
+            _ => {
+                return Err(format!(
+                    "jwt.key_dir and jwt.kid are required for {:?}",
+                    algorithm
+                ))
+            }
+        };
+        let active = Key::from_dir(dir, kid, algorithm)?;
+        if active.encoding.is_none() {
+            return Err(format!("the private key of \"{}\" is missing", kid));
+        }
+        let mut retired = HashMap::new();
+        for entry in fs::read_dir(dir).map_err(|err| err.to_string())? {
+            let name = entry.map_err(|err| err.to_string())?.file_name();
+            let other = match name.to_string_lossy().strip_suffix(".pub.pem") {
+                Some(other) if other != kid => other.to_owned(),
+                _ => continue,
+            };
+            retired.insert(other.clone(), Key::from_dir(dir, &other, algorithm)?);
+        }
+        Ok(Self { active, retired })
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
+        let mut header = Header::new(self.active.algorithm);
+        header.kid = self.active.kid.clone();
+        match &self.active.encoding {
+            Some(key) => encode(&header, claims, key),
+            None => Err(ErrorKind::InvalidKeyFormat.into()),
+        }
+    }
+
+    fn find(&self, kid: Option<&str>) -> Option<&Key> {
+        match kid {
+            Some(kid) if self.active.kid.as_deref() != Some(kid) => self.retired.get(kid),
+            _ => Some(&self.active),
+        }
+    }
+
+    /// Picks the verifying key by the token's `kid`. `configure` adjusts the
+    /// validation, e.g. to require an audience.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        configure: impl FnOnce(&mut Validation),
+    ) -> Result<TokenData<T>, JwtError> {
+        let header = decode_header(token)?;
+        let key = self
+            .find(header.kid.as_deref())
+            .ok_or(ErrorKind::InvalidSignature)?;
+        let mut validation = key.validation();
+        configure(&mut validation);
+        decode(token, &key.decoding, &validation)
+    }
+
+    /// The public keys as a JSON Web Key Set; empty for a shared secret.
+    pub fn jwks(&self) -> serde_json::Value {
+        let keys: Vec<&serde_json::Value> = std::iter::once(&self.active)
+            .chain(self.retired.values())
+            .filter_map(|key| key.jwk.as_ref())
+            .collect();
+        json!({ "keys": keys })
+    }
+}
+
+pub static KEYS: Lazy<KeyRing> =
+    Lazy::new(|| KeyRing::from_config(&APP_CONFIG.jwt).expect("JWT keys initialize fail!"));
@@ -1,12 +1,19 @@
 pub mod auth;
+mod client_ip;
+mod if_match;
 mod json_payload;
+pub mod keys;
 mod pagination;
 mod path;
+pub mod permission;
 mod query;
 
 pub use auth::Claims;
 pub use auth::WeekClaims;
+pub use client_ip::ClientIp;
+pub use if_match::IfMatch;
 pub use json_payload::JsonPayload;
 pub use pagination::Pagination;
 pub use path::Path;
+pub use permission::{perm, Permission, RequirePermission};
 pub use query::Query;
@@ -0,0 +1,147 @@
+use std::{marker::PhantomData, ops::Deref};
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, RequestParts},
+};
+
+use crate::{
+    core::error::{AppError, ErrorCode},
+    e_code_err,
+    entity::{api_token::TokenScope, UserRole},
+};
+
+use super::Claims;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// See drafts, hidden posts, pending comments and protected options.
+    ViewPrivate,
+    WritePosts,
+    EditOthersPosts,
+    ManageTaxonomies,
+    ModerateComments,
+    ManageOptions,
+    ManageUsers,
+}
+
+const AUTHOR: &[Permission] = &[Permission::ViewPrivate, Permission::WritePosts];
+
+const EDITOR: &[Permission] = &[
+    Permission::ViewPrivate,
+    Permission::WritePosts,
+    Permission::EditOthersPosts,
+    Permission::ManageTaxonomies,
+    Permission::ModerateComments,
+];
+
+const ADMIN: &[Permission] = &[
+    Permission::ViewPrivate,
+    Permission::WritePosts,
+    Permission::EditOthersPosts,
+    Permission::ManageTaxonomies,
+    Permission::ModerateComments,
+    Permission::ManageOptions,
+    Permission::ManageUsers,
+];
+
+impl UserRole {
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            UserRole::Visitor => &[],
+            UserRole::Author => AUTHOR,
+            UserRole::Editor => EDITOR,
+            // the site owner from before multi-user accounts existed
+            UserRole::Admin | UserRole::Manager => ADMIN,
+        }
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+impl TokenScope {
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            TokenScope::PostsRead => &[Permission::ViewPrivate],
+            TokenScope::PostsWrite => &[
+                Permission::ViewPrivate,
+                Permission::WritePosts,
+                Permission::EditOthersPosts,
+            ],
+            TokenScope::TaxonomiesWrite => &[Permission::ManageTaxonomies],
+            TokenScope::CommentsModerate => &[Permission::ModerateComments],
+            TokenScope::OptionsWrite => &[Permission::ManageOptions],
+        }
+    }
+
+    /// A scope only narrows what the token owner's role already allows.
+    pub fn grants(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+pub trait PermissionMarker {
+    const PERMISSION: Permission;
+}
+
+pub mod perm {
+    use super::{Permission, PermissionMarker};
+
+    macro_rules! permission_marker {
+        ($($name: ident), *) => {
+            $(
+                pub struct $name;
+
+                impl PermissionMarker for $name {
+                    const PERMISSION: Permission = Permission::$name;
+                }
+            )*
+        };
+    }
+
+    permission_marker!(
+        ViewPrivate,
+        WritePosts,
+        EditOthersPosts,
+        ManageTaxonomies,
+        ModerateComments,
+        ManageOptions,
+        ManageUsers
+    );
+}
+
+/// Rejects the request with `Forbidden` unless the token's role grants `P`.
+pub struct RequirePermission<P: PermissionMarker> {
+    pub claims: Claims,
+    _permission: PhantomData<P>,
+}
+
+impl<P: PermissionMarker> Deref for RequirePermission<P> {
+    type Target = Claims;
+
+    fn deref(&self) -> &Self::Target {
+        &self.claims
+    }
+}
+
+#[async_trait]
+impl<T, P> FromRequest<T> for RequirePermission<P>
+where
+    T: Send + Sync,
+    P: PermissionMarker,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<T>) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request(req).await?;
+        if !claims.can(P::PERMISSION) {
+            return e_code_err!(ErrorCode::Forbidden);
+        }
+        Ok(Self {
+            claims,
+            _permission: PhantomData,
+        })
+    }
+}
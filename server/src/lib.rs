@@ -1,6 +1,8 @@
+pub mod content;
 pub mod core;
 pub mod dto;
 pub mod entity;
 pub mod extract;
 pub mod route;
+pub mod task;
 pub mod utils;
@@ -1,5 +1,8 @@
 use perzine_server::core::{AppState, PGConfig, APP_CONFIG};
+use perzine_server::entity::{login_throttle, user, UserRole};
+use perzine_server::utils::search;
 use sea_orm::{ConnectOptions, Database};
+use std::{net::SocketAddr, sync::Arc};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -15,9 +18,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     opt.min_connections(8).max_connections(16);
     let db = Database::connect(opt).await?;
-    let app = perzine_server::route::init(AppState { db });
+
+    // `perzine_server create-user <email> <nickname> <password> [role]`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [cmd, email, nickname, password, rest @ ..] = args.as_slice() {
+        if cmd == "create-user" {
+            let role = match rest.first() {
+                Some(role) => role.parse::<UserRole>()?,
+                None => UserRole::Admin,
+            };
+            let user = user::create(&db, email, nickname, password, role).await?;
+            println!("user created: {} <{}>", user.nickname, user.email);
+            return Ok(());
+        }
+    }
+
+    login_throttle::ensure_index(&db).await?;
+    if let Err(err) = search::ensure_index(&db).await {
+        eprintln!("search index unavailable, searching will be slow: {}", err);
+    }
+    let state = Arc::new(AppState::new(db));
+    perzine_server::task::scheduler::spawn(state.clone());
+    perzine_server::task::purge::spawn(state.clone());
+    let app = perzine_server::route::init(state);
     axum::Server::bind(&server_conf.addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
     Ok(())
 }
@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::TypedHeader,
+    headers::Cookie,
+    http::{header::SET_COOKIE, HeaderValue},
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, EntityTrait};
+use serde::{Deserialize, Serialize};
+
+use super::oidc;
+use super::utils::{
+    generate_recovery_codes, refresh_session, sign_challenge, start_session, verify_challenge,
+    verify_second_factor, LoginGuard, LoginResult, TokenPair,
+};
+use crate::core::{
+    error::{AppError, ErrorCode},
+    response::HandlerResult,
+    AppState,
+};
+use crate::entity::{session, user};
+use crate::extract::{auth::KEYS, Claims, ClientIp, JsonPayload};
+use crate::utils::totp;
+use crate::{e_code, e_code_err, res, res_ok};
+
+#[derive(Debug, Deserialize)]
+pub struct AuthPayload {
+    email: String,
+    password: String,
+}
+
+pub async fn login(
+    Extension(state): Extension<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    JsonPayload(payload): JsonPayload<AuthPayload>,
+) -> HandlerResult<LoginResult> {
+    let guard = LoginGuard::new(ip, &payload.email);
+    guard.ensure_unlocked(&state.db).await?;
+    let user = match user::find_by_email(&state.db, &payload.email).await? {
+        Some(user) if user.verify_password(&payload.password) => user,
+        found => {
+            if found.is_none() {
+                user::verify_dummy_password(&payload.password);
+            }
+            guard.failed(&state.db).await?;
+            return e_code_err!(ErrorCode::InvalidCredentials);
+        }
+    };
+    // the counters are only cleared once the second factor passed as well,
+    // otherwise re-sending the password would reset the code guessing
+    if user.totp_enabled {
+        return res_ok!(LoginResult::Challenge(sign_challenge(&user)?));
+    }
+    guard.succeeded(&state.db).await?;
+    res_ok!(LoginResult::Tokens(start_session(&state.db, &user).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorLoginPayload {
+    challenge: String,
+    code: String,
+}
+
+pub async fn login_two_factor(
+    Extension(state): Extension<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    JsonPayload(payload): JsonPayload<TwoFactorLoginPayload>,
+) -> HandlerResult<TokenPair> {
+    let uid = verify_challenge(&payload.challenge)?;
+    let user = match user::Entity::find_by_id(uid).one(&state.db).await? {
+        Some(v) if v.totp_enabled => v,
+        _ => return e_code_err!(ErrorCode::InvalidToken),
+    };
+    let guard = LoginGuard::new(ip, &user.email);
+    guard.ensure_unlocked(&state.db).await?;
+    if !verify_second_factor(&state.db, user.clone(), &payload.code).await? {
+        guard.failed(&state.db).await?;
+        return e_code_err!(ErrorCode::InvalidCredentials);
+    }
+    guard.succeeded(&state.db).await?;
+    res_ok!(start_session(&state.db, &user).await?)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshPayload {
+    refresh_token: String,
+}
+
+pub async fn refresh_token(
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(payload): JsonPayload<RefreshPayload>,
+) -> HandlerResult<TokenPair> {
+    res_ok!(refresh_session(&state.db, &payload.refresh_token).await?)
+}
+
+pub async fn logout(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> HandlerResult<()> {
+    claims.ensure_session()?;
+    session::revoke(&state.db, &claims.jti).await?;
+    res_ok!(())
+}
+
+/// Two-factor settings can only be changed from an interactive session.
+async fn session_user(db: &impl ConnectionTrait, claims: &Claims) -> Result<user::Model, AppError> {
+    claims.ensure_session()?;
+    match user::Entity::find_by_id(claims.sub).one(db).await? {
+        Some(v) => Ok(v),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorSetup {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+pub async fn setup_two_factor(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> HandlerResult<TwoFactorSetup> {
+    let user = session_user(&state.db, &claims).await?;
+    if user.totp_enabled {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("two-factor authentication is already enabled.".to_owned())
+        );
+    }
+    let secret = totp::generate_secret();
+    let otpauth_uri = totp::otpauth_uri("perzine", &user.email, &secret);
+    let mut am: user::ActiveModel = user.into();
+    am.totp_secret = ActiveValue::Set(Some(secret.clone()));
+    am.update(&state.db).await?;
+    res_ok!(TwoFactorSetup {
+        secret,
+        otpauth_uri
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CodePayload {
+    code: String,
+}
+
+pub async fn enable_two_factor(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(payload): JsonPayload<CodePayload>,
+) -> HandlerResult<Vec<String>> {
+    let user = session_user(&state.db, &claims).await?;
+    let step = match (&user.totp_secret, user.totp_enabled) {
+        (Some(secret), false) => totp::verify(secret, &payload.code, Utc::now().timestamp()),
+        _ => {
+            return e_code_err!(
+                ErrorCode::InvalidRequest,
+                Some("call /2fa/setup before enabling two-factor authentication.".to_owned())
+            )
+        }
+    };
+    if step.is_none() {
+        return e_code_err!(ErrorCode::InvalidCredentials);
+    }
+    let (codes, hashed) = generate_recovery_codes();
+    let mut am: user::ActiveModel = user.into();
+    am.totp_enabled = ActiveValue::Set(true);
+    am.totp_last_step = ActiveValue::Set(step);
+    am.recovery_codes = ActiveValue::Set(Some(hashed));
+    am.update(&state.db).await?;
+    res_ok!(codes)
+}
+
+pub async fn disable_two_factor(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(payload): JsonPayload<CodePayload>,
+) -> HandlerResult<()> {
+    let user = session_user(&state.db, &claims).await?;
+    if !user.totp_enabled || !verify_second_factor(&state.db, user.clone(), &payload.code).await? {
+        return e_code_err!(ErrorCode::InvalidCredentials);
+    }
+    let mut am: user::ActiveModel = user.into();
+    am.totp_enabled = ActiveValue::Set(false);
+    am.totp_secret = ActiveValue::Set(None);
+    am.totp_last_step = ActiveValue::Set(None);
+    am.recovery_codes = ActiveValue::Set(None);
+    am.update(&state.db).await?;
+    res_ok!(())
+}
+
+pub async fn regenerate_recovery_codes(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(payload): JsonPayload<CodePayload>,
+) -> HandlerResult<Vec<String>> {
+    let user = session_user(&state.db, &claims).await?;
+    if !user.totp_enabled || !verify_second_factor(&state.db, user.clone(), &payload.code).await? {
+        return e_code_err!(ErrorCode::InvalidCredentials);
+    }
+    // the check above may have consumed a recovery code, start from fresh state
+    let user = session_user(&state.db, &claims).await?;
+    let (codes, hashed) = generate_recovery_codes();
+    let mut am: user::ActiveModel = user.into();
+    am.recovery_codes = ActiveValue::Set(Some(hashed));
+    am.update(&state.db).await?;
+    res_ok!(codes)
+}
+
+/// Served bare rather than in a `ResponseBody`, JWKS consumers expect the
+/// standard document.
+pub async fn jwks() -> Json<serde_json::Value> {
+    Json(KEYS.jwks())
+}
+
+#[derive(Serialize)]
+pub struct AuthorizationUrl {
+    pub url: String,
+}
+
+/// Answers with the provider URL and sets the cookie the callback checks, the
+/// front end has to send its requests here with credentials.
+fn authorization_response(
+    authorization: oidc::Authorization,
+) -> Result<impl IntoResponse, AppError> {
+    let cookie = HeaderValue::from_str(&authorization.cookie)
+        .map_err(|_| e_code!(ErrorCode::UnkownError))?;
+    Ok((
+        [(SET_COOKIE, cookie)],
+        res!(AuthorizationUrl {
+            url: authorization.url
+        }),
+    ))
+}
+
+fn browser_of(cookie: &Option<TypedHeader<Cookie>>) -> Option<&str> {
+    cookie
+        .as_ref()
+        .and_then(|TypedHeader(cookie)| cookie.get(oidc::BROWSER_COOKIE))
+}
+
+pub async fn oidc_authorize(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, AppError> {
+    let authorization = oidc::authorize(&state.db, None).await?;
+    authorization_response(authorization)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OIDCCallbackPayload {
+    code: String,
+    state: String,
+}
+
+/// The front end receives the provider redirect and forwards `code` and
+/// `state` here. Accounts with two-factor authentication still get the
+/// challenge.
+pub async fn oidc_callback(
+    Extension(state): Extension<Arc<AppState>>,
+    cookie: Option<TypedHeader<Cookie>>,
+    JsonPayload(payload): JsonPayload<OIDCCallbackPayload>,
+) -> HandlerResult<LoginResult> {
+    let user = oidc::login(
+        &state.db,
+        &payload.code,
+        &payload.state,
+        browser_of(&cookie),
+    )
+    .await?;
+    if user.totp_enabled {
+        return res_ok!(LoginResult::Challenge(sign_challenge(&user)?));
+    }
+    res_ok!(LoginResult::Tokens(start_session(&state.db, &user).await?))
+}
+
+/// Starts an authorization which links the identity to the signed in account.
+pub async fn oidc_link_authorize(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, AppError> {
+    claims.ensure_session()?;
+    let authorization = oidc::authorize(&state.db, Some(claims.sub)).await?;
+    authorization_response(authorization)
+}
+
+pub async fn oidc_link(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    cookie: Option<TypedHeader<Cookie>>,
+    JsonPayload(payload): JsonPayload<OIDCCallbackPayload>,
+) -> HandlerResult<user::Model> {
+    claims.ensure_session()?;
+    let user = oidc::link(
+        &state.db,
+        claims.sub,
+        &payload.code,
+        &payload.state,
+        browser_of(&cookie),
+    )
+    .await?;
+    res_ok!(user)
+}
@@ -1,38 +1,28 @@
-use axum::routing::post;
+use axum::routing::{get, post};
 use axum::Router;
-use jsonwebtoken::{encode, Header};
-use serde::Deserialize;
 
-use crate::core::{error::ErrorCode, response::HandlerResult};
+mod handler;
+mod oidc;
+mod utils;
 
-use crate::extract::{
-    auth::{Claims, KEY},
-    JsonPayload,
+use handler::{
+    disable_two_factor, enable_two_factor, jwks, login, login_two_factor, logout, oidc_authorize,
+    oidc_callback, oidc_link, oidc_link_authorize, refresh_token, regenerate_recovery_codes,
+    setup_two_factor,
 };
-use crate::{e_code, res_ok};
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-pub struct AuthPayload {
-    email: String,
-    password: String,
-}
-
-use crate::core::APP_CONFIG;
-
-pub async fn login(JsonPayload(_payload): JsonPayload<AuthPayload>) -> HandlerResult<String> {
-    let claims = Claims {
-        email: "wayne-wu@163.com".to_owned(),
-        nickname: "wayne".to_owned(),
-        exp: chrono::Local::now().timestamp() + APP_CONFIG.clone().jwt.expires,
-    };
-
-    let token = encode(&Header::default(), &claims, &KEY.encoding)
-        .map_err(|_| e_code!(ErrorCode::TokenCreation))?;
-
-    res_ok!(token)
-}
 
 pub fn get_router() -> Router {
-    Router::new().route("/login", post(login))
+    Router::new()
+        .route("/login", post(login))
+        .route("/login/2fa", post(login_two_factor))
+        .route("/logout", post(logout))
+        .route("/token/refresh", post(refresh_token))
+        .route("/2fa/setup", post(setup_two_factor))
+        .route("/2fa/enable", post(enable_two_factor))
+        .route("/2fa/disable", post(disable_two_factor))
+        .route("/2fa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/.well-known/jwks.json", get(jwks))
+        .route("/oidc/authorize", get(oidc_authorize))
+        .route("/oidc/callback", post(oidc_callback))
+        .route("/oidc/link", get(oidc_link_authorize).post(oidc_link))
 }
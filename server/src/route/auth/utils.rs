@@ -0,0 +1,227 @@
+use std::net::IpAddr;
+
+use axum::http::{header::RETRY_AFTER, HeaderValue};
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, EntityTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        error::{AppError, ErrorCode},
+        APP_CONFIG,
+    },
+    e_code, e_code_err,
+    entity::{
+        login_throttle::{self, ThrottleKind},
+        session, user,
+    },
+    extract::auth::{Claims, KEYS},
+    utils::{random_string, sha256_hex, totp},
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires: i64,
+}
+
+fn sign_access_token(user: &user::Model, session_id: &str) -> Result<(String, i64), AppError> {
+    let exp = Utc::now().timestamp() + APP_CONFIG.jwt.expires;
+    let claims = Claims {
+        sub: user.id,
+        role: user.role.clone(),
+        jti: session_id.to_owned(),
+        email: user.email.clone(),
+        nickname: user.nickname.clone(),
+        exp,
+        scopes: None,
+    };
+    let token = KEYS
+        .encode(&claims)
+        .map_err(|_| e_code!(ErrorCode::TokenCreation))?;
+    Ok((token, exp))
+}
+
+/// Refresh tokens look like `<session id>.<secret>`; only the hash of the
+/// secret is stored.
+fn new_refresh_secret(session_id: &str) -> (String, String) {
+    let secret = random_string(48);
+    (format!("{}.{}", session_id, secret), sha256_hex(&secret))
+}
+
+pub async fn start_session(
+    db: &impl ConnectionTrait,
+    user: &user::Model,
+) -> Result<TokenPair, AppError> {
+    let session_id = random_string(32);
+    let (refresh_token, hashed) = new_refresh_secret(&session_id);
+    let now = Utc::now();
+    session::ActiveModel {
+        id: ActiveValue::Set(session_id.clone()),
+        user_id: ActiveValue::Set(user.id),
+        refresh_token: ActiveValue::Set(hashed),
+        created: ActiveValue::Set(now),
+        expires: ActiveValue::Set(now + Duration::seconds(APP_CONFIG.jwt.refresh_expires)),
+        revoked: ActiveValue::Set(None),
+    }
+    .insert(db)
+    .await?;
+    let (access_token, expires) = sign_access_token(user, &session_id)?;
+    Ok(TokenPair {
+        access_token,
+        refresh_token,
+        expires,
+    })
+}
+
+/// Exchanges a refresh token for a new token pair. Presenting an already
+/// rotated refresh token means it leaked, so the whole session is revoked.
+pub async fn refresh_session(
+    db: &impl ConnectionTrait,
+    refresh_token: &str,
+) -> Result<TokenPair, AppError> {
+    let (session_id, secret) = match refresh_token.split_once('.') {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::InvalidToken),
+    };
+    let target = match session::Entity::find_by_id(session_id.to_owned())
+        .one(db)
+        .await?
+    {
+        Some(v) if v.is_active() => v,
+        Some(_) => return e_code_err!(ErrorCode::RevokedToken),
+        None => return e_code_err!(ErrorCode::InvalidToken),
+    };
+    let user = match user::Entity::find_by_id(target.user_id).one(db).await? {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::InvalidToken),
+    };
+    let (refresh_token, hashed) = new_refresh_secret(session_id);
+    if !session::rotate(db, session_id, &sha256_hex(secret), hashed).await? {
+        session::revoke(db, session_id).await?;
+        return e_code_err!(ErrorCode::RevokedToken);
+    }
+    let (access_token, expires) = sign_access_token(&user, session_id)?;
+    Ok(TokenPair {
+        access_token,
+        refresh_token,
+        expires,
+    })
+}
+
+const CHALLENGE_AUDIENCE: &str = "2fa";
+const CHALLENGE_EXPIRES: i64 = 5 * 60;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Serialize, Deserialize)]
+struct ChallengeClaims {
+    sub: i64,
+    aud: String,
+    exp: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorChallenge {
+    pub challenge: String,
+    pub expires: i64,
+}
+
+/// `/login` answers with one of these, the challenge when the account has
+/// two-factor authentication enabled.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResult {
+    Tokens(TokenPair),
+    Challenge(TwoFactorChallenge),
+}
+
+pub fn sign_challenge(user: &user::Model) -> Result<TwoFactorChallenge, AppError> {
+    let expires = Utc::now().timestamp() + CHALLENGE_EXPIRES;
+    let claims = ChallengeClaims {
+        sub: user.id,
+        aud: CHALLENGE_AUDIENCE.to_owned(),
+        exp: expires,
+    };
+    let challenge = KEYS
+        .encode(&claims)
+        .map_err(|_| e_code!(ErrorCode::TokenCreation))?;
+    Ok(TwoFactorChallenge { challenge, expires })
+}
+
+pub fn verify_challenge(challenge: &str) -> Result<i64, AppError> {
+    let data = KEYS
+        .decode::<ChallengeClaims>(challenge, |validation| {
+            validation.set_audience(&[CHALLENGE_AUDIENCE])
+        })
+        .map_err(|_| e_code!(ErrorCode::InvalidToken))?;
+    Ok(data.claims.sub)
+}
+
+/// Checks `code` as a TOTP code first and as a recovery code second, both are
+/// single use.
+pub async fn verify_second_factor(
+    db: &impl ConnectionTrait,
+    user: user::Model,
+    code: &str,
+) -> Result<bool, AppError> {
+    let secret = match user.totp_secret.clone() {
+        Some(v) => v,
+        None => return Ok(false),
+    };
+    if let Some(step) = totp::verify(&secret, code, Utc::now().timestamp()) {
+        return Ok(user::consume_totp_step(db, user.id, step).await?);
+    }
+    // the codes are generated in lowercase
+    let hashed = sha256_hex(&code.trim().to_lowercase());
+    Ok(user::consume_recovery_code(db, user.id, &hashed).await?)
+}
+
+/// Returns the plain codes and the hashes to store.
+pub fn generate_recovery_codes() -> (Vec<String>, serde_json::Value) {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| random_string(10).to_lowercase())
+        .collect();
+    let hashed: Vec<String> = codes.iter().map(|code| sha256_hex(code)).collect();
+    (codes, serde_json::Value::from(hashed))
+}
+
+/// Login attempts are throttled per client address and per account.
+pub struct LoginGuard {
+    ip: String,
+    account: String,
+}
+
+impl LoginGuard {
+    pub fn new(ip: IpAddr, email: &str) -> Self {
+        Self {
+            ip: ip.to_string(),
+            account: email.trim().to_lowercase(),
+        }
+    }
+
+    pub async fn ensure_unlocked(&self, db: &impl ConnectionTrait) -> Result<(), AppError> {
+        let by_ip = login_throttle::retry_after(db, ThrottleKind::Ip, &self.ip).await?;
+        let by_account =
+            login_throttle::retry_after(db, ThrottleKind::Account, &self.account).await?;
+        match by_ip.max(by_account) {
+            Some(secs) => Err(e_code!(ErrorCode::TooManyAttempts)
+                .with_header(RETRY_AFTER, HeaderValue::from(secs))),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn failed(&self, db: &impl ConnectionTrait) -> Result<(), AppError> {
+        login_throttle::record_failure(db, ThrottleKind::Ip, &self.ip).await?;
+        login_throttle::record_failure(db, ThrottleKind::Account, &self.account).await?;
+        Ok(())
+    }
+
+    pub async fn succeeded(&self, db: &impl ConnectionTrait) -> Result<(), AppError> {
+        login_throttle::clear(db, ThrottleKind::Ip, &self.ip).await?;
+        login_throttle::clear(db, ThrottleKind::Account, &self.account).await?;
+        Ok(())
+    }
+}
@@ -21,9 +21,9 @@ use crate::{
     dto, e_code_err,
     entity::{
         comment::{self, CommentStatus},
-        site_option, UserRole,
+        site_option, trash, UserRole,
     },
-    extract::{Claims, JsonPayload, Pagination, WeekClaims},
+    extract::{perm, JsonPayload, Pagination, Permission, RequirePermission, WeekClaims},
     res_ok,
     utils::SqlOrder,
 };
@@ -66,7 +66,8 @@ pub async fn get_comments(
         cond = cond.add(comment::Column::PostId.eq(pid));
     }
 
-    if !w_claims.is_authed() {
+    let is_authed = w_claims.can(Permission::ModerateComments);
+    if !is_authed {
         cond = cond
             .add(comment::Column::Status.eq(comment::CommentStatus::Published))
             .add(comment::Column::Created.lte(Utc::now()))
@@ -93,14 +94,14 @@ pub async fn get_comments(
         match format.unwrap_or_default() {
             Format::List => {
                 let mut item: dto::comment::Comment = parent.into();
-                item.set_authed(w_claims.is_authed());
+                item.set_authed(is_authed);
                 items.push(item);
             }
             Format::Tree => {
                 let children = list_children(&parent, &state.db, ord.clone()).await?;
                 parent.children = Some(children);
                 let mut item: dto::comment::Comment = parent.into();
-                item.set_authed(w_claims.is_authed());
+                item.set_authed(is_authed);
                 items.push(item);
             }
         }
@@ -129,7 +130,7 @@ pub async fn create_comment(
         .unwrap_or(None)
         .unwrap_or("".to_owned());
     am.post_id = ActiveValue::Set(Some(post_id));
-    if !w_claims.is_authed() {
+    if !w_claims.can(Permission::ModerateComments) {
         let passed = comment::Entity::find()
             .filter(
                 comment::Column::Email
@@ -182,7 +183,7 @@ pub async fn reply_comment(
             .take()
             .unwrap_or(None)
             .unwrap_or("".to_owned());
-        if w_claims.is_authed() {
+        if w_claims.can(Permission::ModerateComments) {
             if am.status.is_not_set() {
                 am.status = ActiveValue::Set(Some(comment::CommentStatus::Published));
             }
@@ -224,14 +225,40 @@ pub async fn reply_comment(
 }
 
 pub async fn update_comment(
-    _claims: Claims,
+    _claims: RequirePermission<perm::ModerateComments>,
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i64>,
     JsonPayload(jv): JsonPayload<serde_json::Value>,
 ) -> HandlerResult<impl Serialize> {
+    let target = match comment::Entity::find_by_id(id).one(&state.db).await? {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
     let mut am = comment::ActiveModel::from_json(jv.clone())?;
     am.id = ActiveValue::Set(id);
     am.modified = ActiveValue::Set(Some(Utc::now()));
+    trash::track(&mut am, &target.status);
     let m = am.update(&state.db).await?;
     res_ok!(dto::comment::Comment::from(m))
 }
+
+pub async fn restore_comment(
+    _claims: RequirePermission<perm::ModerateComments>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<impl Serialize> {
+    let target = match comment::Entity::find_by_id(id).one(&state.db).await? {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    if target.status != Some(CommentStatus::Trashed) {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("only the comment trashed can be restored.".to_owned())
+        );
+    }
+    let m = comment::restore(&state.db, target).await?;
+    let mut m = dto::comment::Comment::from(m);
+    m.set_authed(true);
+    res_ok!(m)
+}
@@ -5,11 +5,12 @@ use axum::{
     routing::{get, post},
     Router,
 };
-use handler::{create_comment, get_comments, reply_comment, update_comment};
+use handler::{create_comment, get_comments, reply_comment, restore_comment, update_comment};
 
 pub fn get_router() -> Router {
     Router::new()
         .route("/", get(get_comments).post(create_comment))
         .route("/:id", post(update_comment))
         .route("/:id/reply", post(reply_comment))
+        .route("/:id/restore", post(restore_comment))
 }
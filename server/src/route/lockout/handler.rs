@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use sea_orm::{EntityTrait, QueryOrder};
+
+use crate::core::{response::HandlerResult, AppState};
+use crate::entity::login_throttle;
+use crate::extract::{perm, Path, RequirePermission};
+use crate::res_ok;
+
+pub async fn get_lockouts(
+    _claims: RequirePermission<perm::ManageUsers>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> HandlerResult<Vec<login_throttle::Model>> {
+    let items = login_throttle::Entity::find()
+        .order_by_desc(login_throttle::Column::LastFailed)
+        .all(&state.db)
+        .await?;
+    res_ok!(items)
+}
+
+pub async fn clear_lockout(
+    _claims: RequirePermission<perm::ManageUsers>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<()> {
+    login_throttle::Entity::delete_by_id(id)
+        .exec(&state.db)
+        .await?;
+    res_ok!(())
+}
+
+pub async fn clear_lockouts(
+    _claims: RequirePermission<perm::ManageUsers>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> HandlerResult<u64> {
+    let res = login_throttle::Entity::delete_many()
+        .exec(&state.db)
+        .await?;
+    res_ok!(res.rows_affected)
+}
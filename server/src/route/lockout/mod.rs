@@ -0,0 +1,14 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+mod handler;
+
+use handler::{clear_lockout, clear_lockouts, get_lockouts};
+
+pub fn get_router() -> Router {
+    Router::new()
+        .route("/", get(get_lockouts).delete(clear_lockouts))
+        .route("/:id", delete(clear_lockout))
+}
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::Extension;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::content::html::is_safe_url;
+use crate::core::{
+    error::{AppError, ErrorCode, FieldError},
+    response::HandlerResult,
+    AppState,
+};
+use crate::dto::menu::{MenuLink, MenuWithItems};
+use crate::entity::{
+    menu,
+    menu_item::{self, MenuItemKind},
+    post, taxonomy,
+};
+use crate::extract::{perm, JsonPayload, Path, Permission, RequirePermission, WeekClaims};
+use crate::{e_code_err, res_ok};
+
+async fn find_menu(db: &impl ConnectionTrait, name: &str) -> Result<menu::Model, AppError> {
+    let item = menu::Entity::find()
+        .filter(menu::Column::Name.eq(name.to_owned()))
+        .one(db)
+        .await?;
+    match item {
+        Some(v) => Ok(v),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+/// Looks the targets of the items up. Items pointing at something gone, or at
+/// a page the caller may not read, are left out.
+async fn resolve(
+    db: &impl ConnectionTrait,
+    items: Vec<menu_item::Model>,
+    is_authed: bool,
+) -> Result<Vec<MenuLink>, AppError> {
+    let ids_of = |kind: MenuItemKind| -> Vec<i64> {
+        items
+            .iter()
+            .filter(|item| item.kind == kind)
+            .filter_map(|item| item.target_id)
+            .collect()
+    };
+    let pages: HashMap<i64, post::Model> = post::Entity::find()
+        .filter(post::Column::Id.is_in(ids_of(MenuItemKind::Page)))
+        .filter(post::Column::IsPage.eq(true))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|page| (page.id, page))
+        .collect();
+    let ancestors = post::ancestors(db, &pages.keys().copied().collect::<Vec<_>>()).await?;
+    let tids: Vec<i32> = ids_of(MenuItemKind::Taxonomy)
+        .into_iter()
+        .filter_map(|id| i32::try_from(id).ok())
+        .collect();
+    let taxonomies: HashMap<i64, taxonomy::Model> = taxonomy::Entity::find()
+        .filter(taxonomy::Column::Id.is_in(tids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|item| (item.id as i64, item))
+        .collect();
+
+    let mut links = Vec::with_capacity(items.len());
+    for item in items {
+        let mut link = MenuLink {
+            id: item.id,
+            kind: item.kind.clone(),
+            label: item.label.clone().unwrap_or_default(),
+            target_id: item.target_id,
+            path: None,
+            taxonomy_type: None,
+            url: None,
+        };
+        let target = item.target_id.unwrap_or_default();
+        match item.kind {
+            MenuItemKind::Page => {
+                let page = match pages.get(&target) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                // hidden as well when a page above it is hidden
+                link.path = match post::page_path(page, &ancestors, is_authed) {
+                    Some(v) => Some(v),
+                    None => continue,
+                };
+                if item.label.is_none() {
+                    link.label = page.title.clone();
+                }
+            }
+            MenuItemKind::Taxonomy => {
+                let tx = match taxonomies.get(&target) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                link.taxonomy_type = Some(tx.t_type.clone());
+                if item.label.is_none() {
+                    link.label = tx.name.clone();
+                }
+            }
+            MenuItemKind::Url => link.url = item.url,
+        }
+        links.push(link);
+    }
+    Ok(links)
+}
+
+pub async fn get_menus(
+    Extension(state): Extension<Arc<AppState>>,
+) -> HandlerResult<Vec<menu::Model>> {
+    let items = menu::Entity::find()
+        .order_by_asc(menu::Column::Name)
+        .all(&state.db)
+        .await?;
+    res_ok!(items)
+}
+
+pub async fn get_menu(
+    w_claims: WeekClaims,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> HandlerResult<MenuWithItems> {
+    let menu = find_menu(&state.db, &name).await?;
+    let items = menu_item::Entity::find()
+        .filter(menu_item::Column::MenuId.eq(menu.id))
+        .order_by_asc(menu_item::Column::Position)
+        .all(&state.db)
+        .await?;
+    let items = resolve(&state.db, items, w_claims.can(Permission::ViewPrivate)).await?;
+    res_ok!(MenuWithItems { menu, items })
+}
+
+#[derive(Deserialize)]
+pub struct CreatePayload {
+    pub name: String,
+}
+
+pub async fn create_menu(
+    _claims: RequirePermission<perm::ManageOptions>,
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(CreatePayload { name }): JsonPayload<CreatePayload>,
+) -> HandlerResult<menu::Model> {
+    let name = name.trim().to_owned();
+    if name.is_empty() {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("name is required.".to_owned())
+        );
+    }
+    if find_menu(&state.db, &name).await.is_ok() {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some(format!("the menu named \"{}\" has been exist.", name))
+        );
+    }
+    let item = menu::ActiveModel {
+        name: ActiveValue::Set(name),
+        created: ActiveValue::Set(Utc::now()),
+        modified: ActiveValue::Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await?;
+    res_ok!(item)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemPayload {
+    pub kind: MenuItemKind,
+    pub target_id: Option<i64>,
+    pub url: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePayload {
+    /// replaces all the items of the menu, in this order
+    pub items: Vec<ItemPayload>,
+}
+
+/// Collects what is wrong with each item, `path` points into the payload.
+async fn check_items(
+    db: &impl ConnectionTrait,
+    items: &[ItemPayload],
+) -> Result<Vec<FieldError>, AppError> {
+    let mut errors = vec![];
+    for (index, item) in items.iter().enumerate() {
+        let mut error = |field: &str, message: &str| {
+            errors.push(FieldError {
+                path: format!("/items/{}/{}", index, field),
+                message: message.to_owned(),
+            })
+        };
+        match item.kind {
+            MenuItemKind::Page | MenuItemKind::Taxonomy if item.target_id.is_none() => {
+                error("targetId", "is required.")
+            }
+            MenuItemKind::Page => {
+                let page = post::Entity::find_by_id(item.target_id.unwrap_or_default())
+                    .one(db)
+                    .await?;
+                if page.is_none_or(|v| v.is_page != Some(true)) {
+                    error("targetId", "is not a page.");
+                }
+            }
+            MenuItemKind::Taxonomy => {
+                let tx = match i32::try_from(item.target_id.unwrap_or_default()) {
+                    Ok(id) => taxonomy::Entity::find_by_id(id).one(db).await?,
+                    Err(_) => None,
+                };
+                if tx.is_none() {
+                    error("targetId", "is not a taxonomy.");
+                }
+            }
+            MenuItemKind::Url => {
+                match item.url.as_deref().map(str::trim) {
+                    Some(url) if !url.is_empty() && is_safe_url(url) => {}
+                    Some(url) if !url.is_empty() => error("url", "is not allowed."),
+                    _ => error("url", "is required."),
+                }
+                if item.label.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                    error("label", "is required.");
+                }
+            }
+        }
+    }
+    Ok(errors)
+}
+
+pub async fn update_menu(
+    _claims: RequirePermission<perm::ManageOptions>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(name): Path<String>,
+    JsonPayload(UpdatePayload { items }): JsonPayload<UpdatePayload>,
+) -> HandlerResult<MenuWithItems> {
+    let errors = check_items(&state.db, &items).await?;
+    if !errors.is_empty() {
+        return Err(AppError::validation(errors));
+    }
+    let txn = state.db.begin().await?;
+    let menu = find_menu(&txn, &name).await?;
+    menu_item::Entity::delete_many()
+        .filter(menu_item::Column::MenuId.eq(menu.id))
+        .exec(&txn)
+        .await?;
+    let ams: Vec<menu_item::ActiveModel> = items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| menu_item::ActiveModel {
+            menu_id: ActiveValue::Set(menu.id),
+            position: ActiveValue::Set(index as i32),
+            kind: ActiveValue::Set(item.kind),
+            target_id: ActiveValue::Set(item.target_id),
+            url: ActiveValue::Set(item.url.map(|v| v.trim().to_owned())),
+            label: ActiveValue::Set(item.label),
+            ..Default::default()
+        })
+        .collect();
+    if !ams.is_empty() {
+        menu_item::Entity::insert_many(ams).exec(&txn).await?;
+    }
+    let mut am: menu::ActiveModel = menu.into();
+    am.modified = ActiveValue::Set(Utc::now());
+    let menu = am.update(&txn).await?;
+    let items = menu_item::Entity::find()
+        .filter(menu_item::Column::MenuId.eq(menu.id))
+        .order_by_asc(menu_item::Column::Position)
+        .all(&txn)
+        .await?;
+    let items = resolve(&txn, items, true).await?;
+    txn.commit().await?;
+    res_ok!(MenuWithItems { menu, items })
+}
+
+pub async fn delete_menu(
+    _claims: RequirePermission<perm::ManageOptions>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> HandlerResult<()> {
+    let txn = state.db.begin().await?;
+    let menu = find_menu(&txn, &name).await?;
+    menu_item::Entity::delete_many()
+        .filter(menu_item::Column::MenuId.eq(menu.id))
+        .exec(&txn)
+        .await?;
+    menu::Entity::delete_by_id(menu.id).exec(&txn).await?;
+    txn.commit().await?;
+    res_ok!(())
+}
@@ -0,0 +1,11 @@
+use axum::{routing::get, Router};
+
+mod handler;
+
+use handler::{create_menu, delete_menu, get_menu, get_menus, update_menu};
+
+pub fn get_router() -> Router {
+    Router::new()
+        .route("/", get(get_menus).post(create_menu))
+        .route("/:name", get(get_menu).put(update_menu).delete(delete_menu))
+}
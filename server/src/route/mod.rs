@@ -1,21 +1,33 @@
 pub mod auth;
 pub mod comment;
+pub mod lockout;
+pub mod menu;
 pub mod option;
 pub mod post;
+pub mod search;
 pub mod taxonomy;
+pub mod token;
+pub mod user;
+
+use std::sync::Arc;
 
 use axum::Extension;
 use axum::Router;
 
 use crate::core::AppState;
 
-pub fn init(state: AppState) -> Router {
+pub fn init(state: Arc<AppState>) -> Router {
     Router::new()
         .nest("/", auth::get_router())
         .nest("/", taxonomy::get_router())
         .nest("/posts", post::get_router())
+        .nest("/search", search::get_router())
         .nest("/comments", comment::get_router())
         .nest("/options", option::get_router())
-        .layer(Extension(std::sync::Arc::new(state)))
+        .nest("/users", user::get_router())
+        .nest("/tokens", token::get_router())
+        .nest("/lockouts", lockout::get_router())
+        .nest("/menus", menu::get_router())
+        .layer(Extension(state))
     // .layer(axum::error_handling::HandleErrorLayer::new(handle_error))
 }
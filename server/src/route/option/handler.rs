@@ -4,7 +4,7 @@ use axum::Extension;
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter};
 
 use crate::entity::site_option::{self, OptionLevel, Utils};
-use crate::extract::{Claims, JsonPayload, WeekClaims};
+use crate::extract::{perm, JsonPayload, Permission, RequirePermission, WeekClaims};
 use crate::{
     core::{error::ErrorCode, response::HandlerResult, AppState},
     extract::Path,
@@ -16,7 +16,7 @@ pub async fn get_options(
     Extension(state): Extension<Arc<AppState>>,
 ) -> HandlerResult<HashMap<String, String>> {
     let opts = site_option::Entity::find().all(&state.db).await?;
-    let filtered = if wc.is_authed() {
+    let filtered = if wc.can(Permission::ViewPrivate) {
         opts.exclude_private()
     } else {
         opts.filter_public()
@@ -39,7 +39,7 @@ pub async fn get_option(
         .await?;
     match opt {
         Some(opt) => {
-            if opt.is_protected() && !week_claims.is_authed() {
+            if opt.is_protected() && !week_claims.can(Permission::ViewPrivate) {
                 return e_code_err!(ErrorCode::NotFound);
             }
             res_ok!(opt.value)
@@ -49,7 +49,7 @@ pub async fn get_option(
 }
 
 pub async fn update_options(
-    _claims: Claims,
+    _claims: RequirePermission<perm::ManageOptions>,
     Extension(state): Extension<Arc<AppState>>,
     JsonPayload(opts): JsonPayload<HashMap<String, String>>,
 ) -> HandlerResult<HashMap<String, String>> {
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use super::utils::{ensure_editable, settle_schedule};
+use crate::core::{
+    error::{AppError, ErrorCode},
+    event::Event,
+    response::HandlerResult,
+    AppState,
+};
+use crate::entity::{
+    post::{self, PostStatus},
+    post_revision, post_taxonomy,
+    taxonomy::{self, TaxonomyType},
+    trash,
+};
+use crate::extract::{perm, Claims, JsonPayload, RequirePermission};
+use crate::{e_code, e_code_err, res_ok};
+
+const MAX_IDS: usize = 500;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BatchAction {
+    SetStatus { status: PostStatus },
+    Trash,
+    Restore,
+    Delete,
+    AddCategories { ids: Vec<i32> },
+    RemoveCategories { ids: Vec<i32> },
+    AddTags { ids: Vec<i32> },
+    RemoveTags { ids: Vec<i32> },
+    SetSeries { id: Option<i32> },
+}
+
+#[derive(Deserialize)]
+pub struct BatchPayload {
+    pub ids: Vec<i64>,
+    pub action: BatchAction,
+}
+
+#[derive(Serialize)]
+pub struct BatchResult {
+    pub id: i64,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchAction {
+    /// The taxonomies the action refers to, checked once up front.
+    fn taxonomies(&self) -> Option<(&[i32], TaxonomyType)> {
+        match self {
+            BatchAction::AddCategories { ids } | BatchAction::RemoveCategories { ids } => {
+                Some((ids, TaxonomyType::Category))
+            }
+            BatchAction::AddTags { ids } | BatchAction::RemoveTags { ids } => {
+                Some((ids, TaxonomyType::Tag))
+            }
+            BatchAction::SetSeries { id: Some(id) } => {
+                Some((std::slice::from_ref(id), TaxonomyType::Series))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Changes the status the way `update_post` does: a revision is kept and a
+/// post published with a future date is scheduled instead.
+async fn set_status(
+    txn: &DatabaseTransaction,
+    claims: &Claims,
+    item: post::Model,
+    status: PostStatus,
+) -> Result<post::Model, AppError> {
+    post_revision::snapshot(txn, &item, Some(claims.sub)).await?;
+    let mut am: post::ActiveModel = item.clone().into();
+    am.status = ActiveValue::Set(Some(status));
+    am.modified = ActiveValue::Set(Some(Utc::now()));
+    trash::track(&mut am, &item.status);
+    let item = am.update(txn).await?;
+    settle_schedule(txn, item).await
+}
+
+/// Applies the action to one post. `Ok(Err(..))` is a refusal reported for the
+/// id, `Err(..)` a failure which aborts the whole batch.
+async fn apply(
+    txn: &DatabaseTransaction,
+    claims: &Claims,
+    item: post::Model,
+    action: &BatchAction,
+) -> Result<Result<Option<post::Model>, String>, AppError> {
+    let id = item.id;
+    if ensure_editable(claims, &item).is_err() {
+        return Ok(Err("you can only edit your own posts.".to_owned()));
+    }
+    let trashed = item.status == Some(PostStatus::Trashed);
+    let item = match action {
+        BatchAction::SetStatus { status } => {
+            if *status == PostStatus::Scheduled && item.published.is_none() {
+                return Ok(Err("published is required for a scheduled post.".to_owned()));
+            }
+            set_status(txn, claims, item, status.clone()).await?
+        }
+        BatchAction::Trash => set_status(txn, claims, item, PostStatus::Trashed).await?,
+        BatchAction::Restore if !trashed => return Ok(Err("post is not trashed.".to_owned())),
+        BatchAction::Restore => post::restore(txn, item).await?,
+        BatchAction::Delete if !trashed => {
+            return Ok(Err("only the post trashed can be deleted.".to_owned()))
+        }
+        BatchAction::Delete => {
+            post::purge(txn, id).await?;
+            return Ok(Ok(None));
+        }
+        BatchAction::AddCategories { ids } => {
+            post_taxonomy::attach(txn, id, ids, TaxonomyType::Category).await?;
+            item
+        }
+        BatchAction::AddTags { ids } => {
+            post_taxonomy::attach(txn, id, ids, TaxonomyType::Tag).await?;
+            item
+        }
+        BatchAction::RemoveCategories { ids } | BatchAction::RemoveTags { ids } => {
+            post_taxonomy::detach(txn, id, ids).await?;
+            item
+        }
+        BatchAction::SetSeries { id: series } => {
+            // staying in the same series keeps the place in it
+            post_taxonomy::Entity::delete_many()
+                .filter(post_taxonomy::Column::PostId.eq(id))
+                .filter(post_taxonomy::Column::TaxonomyType.eq(TaxonomyType::Series))
+                .filter(post_taxonomy::Column::TaxonomyId.ne(series.unwrap_or_default()))
+                .exec(txn)
+                .await?;
+            if let Some(series) = series {
+                post_taxonomy::attach(txn, id, &[*series], TaxonomyType::Series).await?;
+            }
+            item
+        }
+    };
+    if matches!(
+        action,
+        BatchAction::AddCategories { .. }
+            | BatchAction::AddTags { .. }
+            | BatchAction::RemoveCategories { .. }
+            | BatchAction::RemoveTags { .. }
+            | BatchAction::SetSeries { .. }
+    ) {
+        // the terms are part of the post, its ETag has to change with them
+        post::touch(txn, id).await?;
+    }
+    Ok(Ok(Some(item)))
+}
+
+/// Applies one action to many posts in a single transaction. Posts which can
+/// not take the action are skipped and reported, the others are changed.
+pub async fn batch_posts(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(BatchPayload { ids, action }): JsonPayload<BatchPayload>,
+) -> HandlerResult<Vec<BatchResult>> {
+    if ids.is_empty() || ids.len() > MAX_IDS {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some(format!("ids must have 1 to {} items.", MAX_IDS))
+        );
+    }
+    if let Some((tids, t_type)) = action.taxonomies() {
+        let is_valid = taxonomy::is_valid_taxonomy(&state.db, tids.to_vec(), t_type)
+            .await
+            .map_err(|err| e_code!(ErrorCode::DBError, Some(err.to_string())))?;
+        if !is_valid {
+            return e_code_err!(
+                ErrorCode::InvalidRequest,
+                Some("invalid taxonomy.".to_owned())
+            );
+        }
+    }
+    let txn = state.db.begin().await?;
+    let mut report = Vec::with_capacity(ids.len());
+    let mut published = Vec::new();
+    for id in ids.into_iter() {
+        let item = match post::Entity::find_by_id(id).one(&txn).await? {
+            Some(item) => item,
+            None => {
+                report.push(BatchResult {
+                    id,
+                    ok: false,
+                    error: Some("post does not exist.".to_owned()),
+                });
+                continue;
+            }
+        };
+        let before = item.status.clone();
+        let result = apply(&txn, &claims, item, &action).await?;
+        if let Ok(Some(item)) = &result {
+            if item.status == Some(PostStatus::Published) && before != item.status {
+                published.push(Event::PostPublished {
+                    id: item.id,
+                    route: item.route.clone(),
+                });
+            }
+        }
+        report.push(BatchResult {
+            id,
+            ok: result.is_ok(),
+            error: result.err(),
+        });
+    }
+    txn.commit().await?;
+    for event in published.into_iter() {
+        state.emit(event);
+    }
+    res_ok!(report)
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ConnectionTrait, EntityTrait, QuerySelect, TransactionTrait,
+};
+use serde::Serialize;
+
+use super::utils::{ensure_editable, fill_derived, validate_payload};
+use crate::core::{
+    error::{AppError, ErrorCode},
+    response::{HandlerResult, TaggedResult},
+    AppState,
+};
+use crate::dto::post::{FulledPost, PostWithTaxonomy};
+use crate::entity::{post, post_draft, post_revision};
+use crate::extract::{perm, JsonPayload, Path, RequirePermission};
+use crate::{e_code, e_code_err, res_ok, res_tagged};
+
+/// Locks the post row, autosaves and publishing of the same post take turns.
+async fn lock_post(db: &impl ConnectionTrait, id: i64) -> Result<post::Model, AppError> {
+    match post::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+    {
+        Some(v) => Ok(v),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+fn no_draft() -> AppError {
+    e_code!(
+        ErrorCode::NotFound,
+        Some("the post has no draft.".to_owned())
+    )
+}
+
+pub async fn save_draft(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+    JsonPayload(jv): JsonPayload<serde_json::Value>,
+) -> HandlerResult<post_draft::Model> {
+    validate_payload(&jv)?;
+    let am = post_draft::ActiveModel::from_json(jv)?;
+    let txn = state.db.begin().await?;
+    let target = lock_post(&txn, id).await?;
+    ensure_editable(&claims, &target)?;
+    let item = post_draft::save(&txn, &target, am, claims.sub).await?;
+    txn.commit().await?;
+    res_ok!(item)
+}
+
+/// Copies the draft into the post and drops it. The version it replaces is
+/// kept as a revision.
+pub async fn publish_draft(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> TaggedResult<impl Serialize> {
+    let txn = state.db.begin().await?;
+    let target = lock_post(&txn, id).await?;
+    ensure_editable(&claims, &target)?;
+    let draft = match post_draft::Entity::find_by_id(id).one(&txn).await? {
+        Some(v) => v,
+        None => return Err(no_draft()),
+    };
+    post_revision::snapshot(&txn, &target, Some(claims.sub)).await?;
+    let mut am: post::ActiveModel = target.into();
+    am.title = ActiveValue::Set(draft.title);
+    am.subtitle = ActiveValue::Set(draft.subtitle);
+    am.excerpts = ActiveValue::Set(draft.excerpts);
+    am.content = ActiveValue::Set(draft.content);
+    am.extra = ActiveValue::Set(draft.extra);
+    am.modified = ActiveValue::Set(Some(Utc::now()));
+    fill_derived(&mut am, None);
+    let mut item = am.update(&txn).await?;
+    post_draft::Entity::delete_by_id(id).exec(&txn).await?;
+    txn.commit().await?;
+    let etag = item.etag();
+    let txs = item.txs(&state.db).await?;
+    item.comment_count = item.comment_count(&state.db).await?;
+    let mut item = FulledPost::from(item);
+    item.is_authed = true;
+    res_tagged!(etag, PostWithTaxonomy::from_unclassified(item, txs))
+}
+
+pub async fn discard_draft(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<()> {
+    let txn = state.db.begin().await?;
+    let target = lock_post(&txn, id).await?;
+    ensure_editable(&claims, &target)?;
+    let res = post_draft::Entity::delete_by_id(id).exec(&txn).await?;
+    if res.rows_affected == 0 {
+        return Err(no_draft());
+    }
+    txn.commit().await?;
+    res_ok!(())
+}
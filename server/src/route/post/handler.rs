@@ -1,37 +1,72 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use axum::Extension;
+use axum::{extract::OriginalUri, Extension};
 
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, TransactionTrait,
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 
-use super::utils::Filter;
+use super::utils::{
+    check_page_parent, ensure_editable, ensure_route, ensure_visible, fill_derived, moved_to,
+    read_page_fields, series_nav, settle_schedule, summary, validate_payload, Filter, FormatQuery,
+    PreviewQuery,
+};
 use crate::core::{
     error::ErrorCode,
-    response::{HandlerResult, PaginationData},
+    event::Event,
+    response::{HandlerResult, PaginationData, TaggedResult},
     AppState,
 };
 use crate::dto::post::{FulledPost, PostWithTaxonomy, SimplePost};
 use crate::entity::{
-    post, post_taxonomy,
+    post, post_draft, post_revision, post_taxonomy, route_history,
     taxonomy::{self, TaxonomyType},
+    trash,
+};
+use crate::extract::{
+    perm, IfMatch, JsonPayload, Pagination, Path, Permission, Query, RequirePermission, WeekClaims,
 };
-use crate::extract::{Claims, JsonPayload, Pagination, Path, Query, WeekClaims};
-use crate::{e_code_err, res_ok};
+use crate::{e_code_err, res_ok, res_tagged};
 
 pub async fn get_posts(
     w_claims: WeekClaims,
     Extension(state): Extension<Arc<AppState>>,
     Pagination { page, per }: Pagination,
     Query(filter): Query<Filter>,
+) -> HandlerResult<impl Serialize> {
+    let is_authed = w_claims.can(Permission::ViewPrivate);
+    let paginator = post::Entity::find()
+        .filter(filter.condition(is_authed))
+        .order_by(filter.order_by(is_authed), filter.order())
+        .paginate(&state.db, per);
+
+    let total = paginator.num_items().await?;
+    let pages = paginator.num_pages().await?;
+    let items = paginator.fetch_page(page).await?;
+    let mut formatted = Vec::with_capacity(items.len());
+    for mut item in items.into_iter() {
+        let txs = item.txs(&state.db).await?;
+        item.comment_count = item.comment_count(&state.db).await?;
+        let mut item = SimplePost::from(item);
+        item.is_authed = is_authed;
+        formatted.push(PostWithTaxonomy::from_unclassified(item, txs));
+    }
+
+    res_ok!(PaginationData::new(formatted, total, pages))
+}
+
+/// Upcoming posts, the next one to go live first.
+pub async fn get_scheduled_posts(
+    _: RequirePermission<perm::ViewPrivate>,
+    Extension(state): Extension<Arc<AppState>>,
+    Pagination { page, per }: Pagination,
 ) -> HandlerResult<impl Serialize> {
     let paginator = post::Entity::find()
-        .filter(filter.condition(w_claims.is_authed()))
-        .order_by(filter.order_by(w_claims.is_authed()), filter.order())
+        .filter(post::Column::Status.eq(post::PostStatus::Scheduled))
+        .order_by_asc(post::Column::Published)
         .paginate(&state.db, per);
 
     let total = paginator.num_items().await?;
@@ -42,49 +77,169 @@ pub async fn get_posts(
         let txs = item.txs(&state.db).await?;
         item.comment_count = item.comment_count(&state.db).await?;
         let mut item = SimplePost::from(item);
-        item.is_authed = w_claims.is_authed();
+        item.is_authed = true;
         formatted.push(PostWithTaxonomy::from_unclassified(item, txs));
     }
 
     res_ok!(PaginationData::new(formatted, total, pages))
 }
 
+/// The single post as readers get it, whichever way it was looked up.
+async fn post_detail(
+    state: &AppState,
+    w_claims: &WeekClaims,
+    mut item: post::Model,
+    format: &FormatQuery,
+    preview: Option<&str>,
+) -> TaggedResult<PostWithTaxonomy<FulledPost>> {
+    ensure_visible(&state.db, w_claims, &item, preview).await?;
+    let etag = item.etag();
+    item.comment_count = item.comment_count(&state.db).await?;
+    let txs = item.txs(&state.db).await?;
+    let is_authed = w_claims.can(Permission::ViewPrivate);
+    let nav = series_nav(&state.db, &item, &txs, is_authed).await?;
+    let mut item = FulledPost::from(item);
+    item.is_authed = is_authed;
+    if item.is_authed {
+        item.draft = post_draft::Entity::find_by_id(item.id)
+            .one(&state.db)
+            .await?;
+    }
+    item.content = format.render(item.content)?;
+    let mut item = PostWithTaxonomy::from_unclassified(item, txs);
+    item.series_nav = nav;
+    res_tagged!(etag, item)
+}
+
 pub async fn get_post_by_id(
     w_claims: WeekClaims,
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i64>,
-) -> HandlerResult<impl Serialize> {
+    Query(format): Query<FormatQuery>,
+    Query(PreviewQuery { preview }): Query<PreviewQuery>,
+) -> TaggedResult<impl Serialize> {
     let item = post::Entity::find()
         .filter(post::Column::Id.eq(id))
         .one(&state.db)
         .await?;
-    if let Some(mut item) = item {
-        item.comment_count = item.comment_count(&state.db).await?;
-        let txs = item.txs(&state.db).await?;
-        let mut item = FulledPost::from(item);
-        item.is_authed = w_claims.is_authed();
-        return res_ok!(PostWithTaxonomy::from_unclassified(item, txs));
+    match item {
+        Some(item) => post_detail(&state, &w_claims, item, &format, preview.as_deref()).await,
+        None => e_code_err!(ErrorCode::NotFound),
     }
-    e_code_err!(ErrorCode::NotFound)
 }
 
 pub async fn get_post_by_route(
     w_claims: WeekClaims,
     Extension(state): Extension<Arc<AppState>>,
+    OriginalUri(uri): OriginalUri,
     Path(route): Path<String>,
-) -> HandlerResult<impl Serialize> {
+    Query(format): Query<FormatQuery>,
+    Query(PreviewQuery { preview }): Query<PreviewQuery>,
+) -> TaggedResult<impl Serialize> {
     let item = post::Entity::find()
-        .filter(post::Column::Route.eq(route))
+        .filter(post::Column::Route.eq(route.clone()))
         .one(&state.db)
         .await?;
-    if let Some(mut item) = item {
-        item.comment_count = item.comment_count(&state.db).await?;
-        let txs = item.txs(&state.db).await?;
-        let mut item = FulledPost::from(item);
-        item.is_authed = w_claims.is_authed();
-        return res_ok!(PostWithTaxonomy::from_unclassified(item, txs));
+    if let Some(item) = item {
+        return post_detail(&state, &w_claims, item, &format, preview.as_deref()).await;
+    }
+    let moved = match route_history::find_by_route(&state.db, &route).await? {
+        Some(history) => {
+            post::Entity::find_by_id(history.post_id)
+                .one(&state.db)
+                .await?
+        }
+        None => None,
+    };
+    match moved {
+        // the redirect would tell where a hidden post lives now
+        Some(item) => {
+            ensure_visible(&state.db, &w_claims, &item, preview.as_deref()).await?;
+            match item.route {
+                Some(current) => Err(moved_to(&uri, &current)),
+                None => e_code_err!(ErrorCode::NotFound),
+            }
+        }
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+/// Looks a page up by the routes of its ancestors and its own, such as
+/// `about/team`.
+pub async fn get_page_by_path(
+    w_claims: WeekClaims,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(path): Path<String>,
+    Query(format): Query<FormatQuery>,
+    Query(PreviewQuery { preview }): Query<PreviewQuery>,
+) -> TaggedResult<impl Serialize> {
+    let segments: Vec<&str> = path.split('/').filter(|v| !v.is_empty()).collect();
+    let mut pages: HashMap<String, post::Model> = post::Entity::find()
+        .filter(post::Column::Route.is_in(segments.iter().copied()))
+        .filter(post::Column::IsPage.eq(true))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .filter_map(|item| item.route.clone().map(|route| (route, item)))
+        .collect();
+    // a page below a hidden one is only reached by those who see the hidden
+    // one, a preview token only opens the page it was minted for
+    let is_authed = w_claims.can(Permission::ViewPrivate);
+    let mut found: Option<post::Model> = None;
+    for segment in segments {
+        if found.as_ref().is_some_and(|v| !is_authed && !v.is_public()) {
+            return e_code_err!(ErrorCode::NotFound);
+        }
+        match pages.remove(segment) {
+            Some(item) if item.parent_id == found.as_ref().map(|v| v.id) => found = Some(item),
+            _ => return e_code_err!(ErrorCode::NotFound),
+        }
+    }
+    match found {
+        Some(item) => post_detail(&state, &w_claims, item, &format, preview.as_deref()).await,
+        None => e_code_err!(ErrorCode::NotFound),
     }
-    e_code_err!(ErrorCode::NotFound)
+}
+
+/// The pages right below the page, in their sort order.
+pub async fn get_subpages(
+    w_claims: WeekClaims,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<Vec<SimplePost>> {
+    let is_authed = w_claims.can(Permission::ViewPrivate);
+    let parent = post::Entity::find_by_id(id)
+        .filter(post::Column::IsPage.eq(true))
+        .one(&state.db)
+        .await?;
+    let parent = match parent {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    // the pages below a hidden page are hidden as well
+    if !is_authed {
+        let ancestors = post::ancestors(&state.db, &[id]).await?;
+        if !parent.is_public() || !ancestors.values().all(post::Model::is_public) {
+            return e_code_err!(ErrorCode::NotFound);
+        }
+    }
+    let mut cond = Condition::all()
+        .add(post::Column::ParentId.eq(id))
+        .add(post::Column::IsPage.eq(true));
+    if !is_authed {
+        cond = cond.add(post::public_condition());
+    }
+    let items = post::Entity::find()
+        .filter(cond)
+        .order_by_asc(post::Column::SortOrder)
+        .order_by_asc(post::Column::Id)
+        .all(&state.db)
+        .await?;
+    let mut formatted = Vec::with_capacity(items.len());
+    for item in items {
+        formatted.push(summary(&state.db, item, is_authed).await?);
+    }
+    res_ok!(formatted)
 }
 
 #[derive(Deserialize)]
@@ -95,18 +250,25 @@ pub struct ExtraPayload {
 }
 
 pub async fn create_post(
-    _claims: Claims,
+    claims: RequirePermission<perm::WritePosts>,
     Extension(state): Extension<Arc<AppState>>,
     JsonPayload(jv): JsonPayload<serde_json::Value>,
 ) -> HandlerResult<impl Serialize> {
+    validate_payload(&jv)?;
     let ExtraPayload {
         categories,
         tags,
         series,
     } = serde_json::from_value(jv.clone())?;
-    let am = post::ActiveModel::from_json(jv.clone())?;
+    let mut am = post::ActiveModel::from_json(jv.clone())?;
+    read_page_fields(&jv, &mut am)?;
+    am.author_id = ActiveValue::Set(Some(claims.sub));
     let txn = state.db.begin().await?;
-    let mut item = am.insert(&state.db).await?;
+    check_page_parent(&txn, &am, None).await?;
+    ensure_route(&txn, &mut am, None).await?;
+    fill_derived(&mut am, None);
+    let item = am.insert(&txn).await?;
+    let mut item = settle_schedule(&txn, item).await?;
     if categories.is_none() {
         return e_code_err!(
             ErrorCode::InvalidRequest,
@@ -123,6 +285,12 @@ pub async fn create_post(
         post_taxonomy::update(&txn, item.id, vec![tid], TaxonomyType::Series).await?;
     }
     txn.commit().await?;
+    if item.status == Some(post::PostStatus::Published) {
+        state.emit(Event::PostPublished {
+            id: item.id,
+            route: item.route.clone(),
+        });
+    }
 
     let txs = item.txs(&state.db).await?;
     item.comment_count = item.comment_count(&state.db).await?;
@@ -133,21 +301,48 @@ pub async fn create_post(
 }
 
 pub async fn update_post(
-    _claims: Claims,
+    claims: RequirePermission<perm::WritePosts>,
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i64>,
+    if_match: IfMatch,
     JsonPayload(jv): JsonPayload<serde_json::Value>,
-) -> HandlerResult<impl Serialize> {
+) -> TaggedResult<impl Serialize> {
+    let txn = (&state.db).begin().await?;
+    // locked until the commit, so no one can sneak in between check and write
+    let target = post::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+    let target = match target {
+        Some(target) => target,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    ensure_editable(&claims, &target)?;
+    if_match.check(&target.etag())?;
+    validate_payload(&jv)?;
     let ExtraPayload {
         categories,
         tags,
         series,
     } = serde_json::from_value(jv.clone())?;
     let mut am = post::ActiveModel::from_json(jv.clone())?;
+    read_page_fields(&jv, &mut am)?;
     am.modified = ActiveValue::Set(Some(Utc::now()));
     am.id = ActiveValue::Set(id);
-    let txn = (&state.db).begin().await?;
-    let mut item = am.update(&txn).await?;
+    check_page_parent(&txn, &am, Some(&target)).await?;
+    post_revision::snapshot(&txn, &target, Some(claims.sub)).await?;
+    ensure_route(&txn, &mut am, Some(&target)).await?;
+    fill_derived(&mut am, Some(&target));
+    trash::track(&mut am, &target.status);
+    let item = am.update(&txn).await?;
+    let mut item = settle_schedule(&txn, item).await?;
+    if let Some(old) = target
+        .route
+        .as_deref()
+        .filter(|old| item.route.as_deref() != Some(*old))
+    {
+        route_history::record(&txn, id, old, item.route.as_deref()).await?;
+    }
     if let Some(tids) = categories {
         post_taxonomy::update(&txn, id, tids, taxonomy::TaxonomyType::Category).await?;
     }
@@ -158,6 +353,39 @@ pub async fn update_post(
         post_taxonomy::update(&txn, id, vec![tid], taxonomy::TaxonomyType::Series).await?;
     }
     txn.commit().await?;
+    let published = Some(post::PostStatus::Published);
+    if item.status == published && target.status != published {
+        state.emit(Event::PostPublished {
+            id: item.id,
+            route: item.route.clone(),
+        });
+    }
+    let etag = item.etag();
+    let txs = item.txs(&state.db).await?;
+    item.comment_count = item.comment_count(&state.db).await?;
+    let mut item = FulledPost::from(item);
+    item.is_authed = true;
+    item.draft = post_draft::Entity::find_by_id(id).one(&state.db).await?;
+    res_tagged!(etag, PostWithTaxonomy::from_unclassified(item, txs))
+}
+
+pub async fn restore_post(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<impl Serialize> {
+    let target = match post::Entity::find_by_id(id).one(&state.db).await? {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    ensure_editable(&claims, &target)?;
+    if target.status != Some(post::PostStatus::Trashed) {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("only the post trashed can be restored.".to_owned())
+        );
+    }
+    let mut item = post::restore(&state.db, target).await?;
     let txs = item.txs(&state.db).await?;
     item.comment_count = item.comment_count(&state.db).await?;
     let mut item = FulledPost::from(item);
@@ -166,13 +394,14 @@ pub async fn update_post(
 }
 
 pub async fn delete_post(
-    _claims: Claims,
+    claims: RequirePermission<perm::WritePosts>,
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i64>,
 ) -> HandlerResult<()> {
     let target = post::Entity::find_by_id(id).one(&state.db).await?;
     match target {
         Some(v) => {
+            ensure_editable(&claims, &v)?;
             if !v.status.eq(&Some(post::PostStatus::Trashed)) {
                 return e_code_err!(
                     ErrorCode::InvalidRequest,
@@ -183,11 +412,7 @@ pub async fn delete_post(
         None => return e_code_err!(ErrorCode::NotFound),
     }
     let txn = (state.db).begin().await?;
-    post_taxonomy::Entity::delete_many()
-        .filter(post_taxonomy::Column::PostId.eq(id))
-        .exec(&txn)
-        .await?;
-    post::Entity::delete_by_id(id).exec(&txn).await?;
+    post::purge(&txn, id).await?;
     txn.commit().await?;
     res_ok!(())
 }
@@ -1,11 +1,27 @@
-use axum::{routing::get, Router};
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
 
+mod batch;
+mod draft;
 mod handler;
+mod preview;
+mod related;
+mod revision;
 mod utils;
 
+pub use utils::{summary, Filter};
+
+use batch::batch_posts;
+use draft::{discard_draft, publish_draft, save_draft};
 use handler::{
-    create_post, delete_post, get_post_by_id, get_post_by_route, get_posts, update_post,
+    create_post, delete_post, get_page_by_path, get_post_by_id, get_post_by_route, get_posts,
+    get_scheduled_posts, get_subpages, restore_post, update_post,
 };
+use preview::{create_preview, get_previews, revoke_preview};
+use related::get_related_posts;
+use revision::{diff_revision, get_revision, get_revisions, restore_revision};
 
 pub fn get_router() -> Router {
     Router::new()
@@ -15,4 +31,18 @@ pub fn get_router() -> Router {
             get(get_post_by_id).put(update_post).delete(delete_post),
         )
         .route("/route/:route", get(get_post_by_route))
+        .route("/path/*path", get(get_page_by_path))
+        .route("/scheduled", get(get_scheduled_posts))
+        .route("/batch", post(batch_posts))
+        .route("/:id/restore", post(restore_post))
+        .route("/:id/draft", put(save_draft).delete(discard_draft))
+        .route("/:id/draft/publish", post(publish_draft))
+        .route("/:id/previews", get(get_previews).post(create_preview))
+        .route("/:id/previews/:pid", delete(revoke_preview))
+        .route("/:id/related", get(get_related_posts))
+        .route("/:id/children", get(get_subpages))
+        .route("/:id/revisions", get(get_revisions))
+        .route("/:id/revisions/:rid", get(get_revision))
+        .route("/:id/revisions/:rid/diff", get(diff_revision))
+        .route("/:id/revisions/:rid/restore", post(restore_revision))
 }
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+
+use super::utils::ensure_editable;
+use crate::core::{
+    error::{AppError, ErrorCode},
+    response::HandlerResult,
+    AppState,
+};
+use crate::entity::{post, preview_token};
+use crate::extract::{perm, JsonPayload, Path, RequirePermission};
+use crate::utils::{random_string, sha256_hex};
+use crate::{e_code_err, res_ok};
+
+/// how long a preview link works unless told otherwise, in days
+const DEFAULT_EXPIRES: i64 = 7;
+
+async fn find_post(db: &impl ConnectionTrait, id: i64) -> Result<post::Model, AppError> {
+    match post::Entity::find_by_id(id).one(db).await? {
+        Some(v) => Ok(v),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+pub async fn get_previews(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<Vec<preview_token::Model>> {
+    let target = find_post(&state.db, id).await?;
+    ensure_editable(&claims, &target)?;
+    let items = preview_token::Entity::find()
+        .filter(preview_token::Column::PostId.eq(id))
+        .order_by_desc(preview_token::Column::Created)
+        .all(&state.db)
+        .await?;
+    res_ok!(items)
+}
+
+#[derive(Deserialize)]
+pub struct CreatePayload {
+    pub expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedPreview {
+    /// the plain token, it is only ever shown once, pass it as `?preview=`
+    pub token: String,
+    #[serde(flatten)]
+    pub item: preview_token::Model,
+}
+
+pub async fn create_preview(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+    JsonPayload(payload): JsonPayload<CreatePayload>,
+) -> HandlerResult<CreatedPreview> {
+    let target = find_post(&state.db, id).await?;
+    ensure_editable(&claims, &target)?;
+    let now = Utc::now();
+    let expires = payload
+        .expires
+        .unwrap_or_else(|| now + Duration::days(DEFAULT_EXPIRES));
+    if expires <= now {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("expires must be in the future.".to_owned())
+        );
+    }
+    let token = random_string(40);
+    let item = preview_token::ActiveModel {
+        post_id: ActiveValue::Set(id),
+        token: ActiveValue::Set(sha256_hex(&token)),
+        created_by: ActiveValue::Set(claims.sub),
+        created: ActiveValue::Set(now),
+        expires: ActiveValue::Set(expires),
+        revoked: ActiveValue::Set(None),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await?;
+    res_ok!(CreatedPreview { token, item })
+}
+
+pub async fn revoke_preview(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path((id, pid)): Path<(i64, i64)>,
+) -> HandlerResult<()> {
+    let target = find_post(&state.db, id).await?;
+    ensure_editable(&claims, &target)?;
+    let item = preview_token::Entity::find_by_id(pid)
+        .filter(preview_token::Column::PostId.eq(id))
+        .one(&state.db)
+        .await?;
+    let item = match item {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    let mut am: preview_token::ActiveModel = item.into();
+    am.revoked = ActiveValue::Set(Some(Utc::now()));
+    am.update(&state.db).await?;
+    res_ok!(())
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use sea_orm::{
+    sea_query::{Alias, Expr, Query as SqlQuery},
+    ColumnTrait, Condition, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait,
+};
+use serde::Deserialize;
+
+use super::utils::{ensure_visible, PreviewQuery};
+use crate::core::{error::ErrorCode, response::HandlerResult, AppState};
+use crate::dto::post::SimplePost;
+use crate::entity::{post, post_taxonomy};
+use crate::extract::{Path, Permission, Query, WeekClaims};
+use crate::{e_code_err, res_ok};
+
+const DEFAULT_LIMIT: u64 = 5;
+const MAX_LIMIT: u64 = 20;
+
+/// Sums the terms a candidate shares with the post. A shared series outweighs
+/// a category, which outweighs a couple of shared tags.
+const SCORE: &str = r#"SUM(CASE "post_taxonomy"."taxonomy_type" WHEN 'series' THEN 8 WHEN 'category' THEN 3 ELSE 1 END)"#;
+
+#[derive(Deserialize)]
+pub struct RelatedQuery {
+    pub limit: Option<u64>,
+}
+
+/// Published posts sharing terms with the post, best match first and the
+/// newer one on a tie.
+pub async fn get_related_posts(
+    w_claims: WeekClaims,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(RelatedQuery { limit }): Query<RelatedQuery>,
+    Query(PreviewQuery { preview }): Query<PreviewQuery>,
+) -> HandlerResult<Vec<SimplePost>> {
+    let target = match post::Entity::find_by_id(id).one(&state.db).await? {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    ensure_visible(&state.db, &w_claims, &target, preview.as_deref()).await?;
+    let mine = Alias::new("mine");
+    let shared = SqlQuery::select()
+        .column((mine.clone(), post_taxonomy::Column::TaxonomyId))
+        .from_as(post_taxonomy::Entity, mine.clone())
+        .and_where(Expr::tbl(mine, post_taxonomy::Column::PostId).eq(id))
+        .to_owned();
+    let items = post::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            post_taxonomy::Relation::Post.def().rev(),
+        )
+        .filter(
+            Condition::all()
+                .add(post_taxonomy::Column::TaxonomyId.in_subquery(shared))
+                .add(post::Column::Id.ne(id))
+                .add(post::public_condition())
+                .add(post::Column::IsPage.eq(false)),
+        )
+        .group_by(post::Column::Id)
+        .order_by_desc(Expr::cust(SCORE))
+        .order_by_desc(post::Column::Published)
+        .order_by_desc(post::Column::Id)
+        .limit(limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+        .all(&state.db)
+        .await?;
+
+    let is_authed = w_claims.can(Permission::ViewPrivate);
+    let mut formatted = Vec::with_capacity(items.len());
+    for mut item in items.into_iter() {
+        item.comment_count = item.comment_count(&state.db).await?;
+        let mut item = SimplePost::from(item);
+        item.is_authed = is_authed;
+        formatted.push(item);
+    }
+    res_ok!(formatted)
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use super::utils::{ensure_editable, fill_derived};
+use crate::core::{
+    error::{AppError, ErrorCode},
+    response::{HandlerResult, PaginationData, TaggedResult},
+    AppState,
+};
+use crate::dto::post::{FulledPost, PostWithTaxonomy};
+use crate::entity::{post, post_revision};
+use crate::extract::{perm, IfMatch, Pagination, Path, Query, RequirePermission};
+use crate::utils::json_diff;
+use crate::{e_code_err, res_ok, res_tagged};
+
+async fn find_post(db: &impl ConnectionTrait, id: i64) -> Result<post::Model, AppError> {
+    match post::Entity::find_by_id(id).one(db).await? {
+        Some(v) => Ok(v),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+async fn find_revision(
+    db: &impl ConnectionTrait,
+    id: i64,
+    rid: i64,
+) -> Result<post_revision::Model, AppError> {
+    let item = post_revision::Entity::find_by_id(rid)
+        .filter(post_revision::Column::PostId.eq(id))
+        .one(db)
+        .await?;
+    match item {
+        Some(v) => Ok(v),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+pub async fn get_revisions(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Pagination { page, per }: Pagination,
+    Path(id): Path<i64>,
+) -> HandlerResult<impl Serialize> {
+    let target = find_post(&state.db, id).await?;
+    ensure_editable(&claims, &target)?;
+    let paginator = post_revision::Entity::find()
+        .filter(post_revision::Column::PostId.eq(id))
+        .order_by_desc(post_revision::Column::Id)
+        .paginate(&state.db, per);
+    let total = paginator.num_items().await?;
+    let pages = paginator.num_pages().await?;
+    let items = paginator.fetch_page(page).await?;
+    res_ok!(PaginationData::new(items, total, pages))
+}
+
+pub async fn get_revision(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path((id, rid)): Path<(i64, i64)>,
+) -> HandlerResult<impl Serialize> {
+    let target = find_post(&state.db, id).await?;
+    ensure_editable(&claims, &target)?;
+    res_ok!(find_revision(&state.db, id, rid).await?)
+}
+
+#[derive(Deserialize)]
+pub struct DiffQuery {
+    /// the revision to compare against, the current post if absent
+    pub to: Option<i64>,
+}
+
+pub async fn diff_revision(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path((id, rid)): Path<(i64, i64)>,
+    Query(DiffQuery { to }): Query<DiffQuery>,
+) -> HandlerResult<impl Serialize> {
+    let target = find_post(&state.db, id).await?;
+    ensure_editable(&claims, &target)?;
+    let from = find_revision(&state.db, id, rid).await?;
+    let to = match to {
+        Some(to) => find_revision(&state.db, id, to).await?.document(),
+        None => post_revision::document_of(&target),
+    };
+    res_ok!(json_diff::diff(&from.document(), &to))
+}
+
+/// Brings a revision back as the current version. The version it replaces is
+/// kept as a revision of its own, so a restore can be undone.
+pub async fn restore_revision(
+    claims: RequirePermission<perm::WritePosts>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path((id, rid)): Path<(i64, i64)>,
+    if_match: IfMatch,
+) -> TaggedResult<impl Serialize> {
+    let txn = state.db.begin().await?;
+    // locked like in `update_post`, the check and the write can not interleave
+    let target = post::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+    let target = match target {
+        Some(target) => target,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    ensure_editable(&claims, &target)?;
+    if_match.check(&target.etag())?;
+    let revision = find_revision(&txn, id, rid).await?;
+    post_revision::snapshot(&txn, &target, Some(claims.sub)).await?;
+    let mut am: post::ActiveModel = target.into();
+    am.title = ActiveValue::Set(revision.title);
+    am.subtitle = ActiveValue::Set(revision.subtitle);
+    am.excerpts = ActiveValue::Set(revision.excerpts);
+    am.content = ActiveValue::Set(revision.content);
+    am.extra = ActiveValue::Set(revision.extra);
+    am.modified = ActiveValue::Set(Some(Utc::now()));
+    fill_derived(&mut am, None);
+    let mut item = am.update(&txn).await?;
+    txn.commit().await?;
+    let etag = item.etag();
+    let txs = item.txs(&state.db).await?;
+    item.comment_count = item.comment_count(&state.db).await?;
+    let mut item = FulledPost::from(item);
+    item.is_authed = true;
+    res_tagged!(etag, PostWithTaxonomy::from_unclassified(item, txs))
+}
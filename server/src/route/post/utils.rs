@@ -1,9 +1,26 @@
+use std::collections::HashSet;
+
+use axum::http::{header, HeaderValue, Uri};
 use chrono::Utc;
-use sea_orm::ColumnTrait;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait,
+    QueryFilter,
+};
 use serde::Deserialize;
 use serde_enum_str::Deserialize_enum_str;
 
-use crate::{entity::post, utils::SqlOrder};
+use crate::{
+    content::{
+        stats::{self, Stats},
+        text, validate, ContentFormat, Document,
+    },
+    core::error::{AppError, ErrorCode},
+    dto::post::{SeriesNav, SimplePost},
+    e_code, e_code_err,
+    entity::{post, post_taxonomy, preview_token, route_history, taxonomy},
+    extract::{Claims, Permission, WeekClaims},
+    utils::{search, sha256_hex, slug::slugify, SqlOrder},
+};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +34,8 @@ pub struct Filter {
     pub published_to: Option<chrono::DateTime<Utc>>,
     pub status: Option<post::PostStatus>,
     pub keyword: Option<String>,
+    /// a category, the ones below it included
+    pub category: Option<i32>,
     pub order_by: Option<OrderKey>,
     pub order: Option<SqlOrder>,
 }
@@ -25,7 +44,10 @@ impl Filter {
     pub fn condition(&self, is_authed: bool) -> sea_orm::Condition {
         let mut cond = sea_orm::Condition::all();
         if let Some(v) = self.keyword.clone() {
-            cond = cond.add(post::Column::Title.like(&v));
+            cond = cond.add(search::matches(&v));
+        }
+        if let Some(v) = self.category {
+            cond = cond.add(post_taxonomy::in_category_tree(v));
         }
         if let Some(v) = self.modified_from {
             cond = cond.add(post::Column::Modified.gte(v));
@@ -72,6 +94,29 @@ impl Filter {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct FormatQuery {
+    pub format: Option<ContentFormat>,
+}
+
+impl FormatQuery {
+    pub fn render(
+        &self,
+        content: Option<serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>, AppError> {
+        self.format
+            .clone()
+            .unwrap_or_default()
+            .render(content)
+            .map_err(|err| {
+                e_code!(
+                    ErrorCode::DataParsingError,
+                    Some(format!("content does not follow the block schema: {}", err))
+                )
+            })
+    }
+}
+
 #[derive(Debug, Clone, Deserialize_enum_str)]
 #[serde(rename_all = "camelCase")]
 pub enum OrderKey {
@@ -101,3 +146,316 @@ impl Default for OrderKey {
         Self::Published
     }
 }
+
+/// A scheduled post needs a publish time, a published one dated in the future
+/// becomes scheduled so `task::scheduler` announces it when it goes live.
+pub async fn settle_schedule(
+    db: &impl ConnectionTrait,
+    item: post::Model,
+) -> Result<post::Model, AppError> {
+    match (&item.status, item.published) {
+        (Some(post::PostStatus::Scheduled), None) => e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("published is required for a scheduled post.".to_owned())
+        ),
+        (Some(post::PostStatus::Published), Some(published)) if published > Utc::now() => {
+            let mut am: post::ActiveModel = item.into();
+            am.status = ActiveValue::Set(Some(post::PostStatus::Scheduled));
+            Ok(am.update(db).await?)
+        }
+        _ => Ok(item),
+    }
+}
+
+/// The first of `base`, `base-2`, `base-3`... which neither another post nor
+/// an old route of another post uses.
+pub async fn unique_route(
+    db: &impl ConnectionTrait,
+    base: &str,
+    id: Option<i64>,
+) -> Result<String, DbErr> {
+    // one `Condition::all` on purpose, a `filter` chained after a
+    // `Condition::any` would be joined with `OR`
+    let mut taken = HashSet::new();
+    let posts = post::Entity::find()
+        .filter(
+            Condition::all()
+                .add(
+                    Condition::any()
+                        .add(post::Column::Route.eq(base))
+                        .add(post::Column::Route.starts_with(&format!("{}-", base))),
+                )
+                .add_option(id.map(|id| post::Column::Id.ne(id))),
+        )
+        .all(db)
+        .await?;
+    taken.extend(posts.into_iter().filter_map(|v| v.route));
+    let history = route_history::Entity::find()
+        .filter(
+            Condition::all()
+                .add(
+                    Condition::any()
+                        .add(route_history::Column::Route.eq(base))
+                        .add(route_history::Column::Route.starts_with(&format!("{}-", base))),
+                )
+                .add_option(id.map(|id| route_history::Column::PostId.ne(id))),
+        )
+        .all(db)
+        .await?;
+    taken.extend(history.into_iter().map(|v| v.route));
+    let mut route = base.to_owned();
+    let mut n = 1;
+    while taken.contains(&route) {
+        n += 1;
+        route = format!("{}-{}", base, n);
+    }
+    Ok(route)
+}
+
+/// Generates the route from the title when the payload leaves it empty. On
+/// updates an absent route is kept, only an explicit empty one is replaced.
+pub async fn ensure_route(
+    db: &impl ConnectionTrait,
+    am: &mut post::ActiveModel,
+    target: Option<&post::Model>,
+) -> Result<(), DbErr> {
+    let missing = match &am.route {
+        ActiveValue::Set(v) => v.as_deref().is_none_or(|v| v.trim().is_empty()),
+        _ => target.is_none(),
+    };
+    if !missing {
+        return Ok(());
+    }
+    let title = match (&am.title, target) {
+        (ActiveValue::Set(title), _) => title.clone(),
+        (_, Some(target)) => target.title.clone(),
+        _ => String::new(),
+    };
+    let route = unique_route(db, &slugify(&title), target.map(|v| v.id)).await?;
+    am.route = ActiveValue::Set(Some(route));
+    Ok(())
+}
+
+/// A permanent redirect from an old route, the last segment of the requested
+/// path is swapped for the current route.
+pub fn moved_to(uri: &Uri, route: &str) -> AppError {
+    let path = uri.path();
+    let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+    let mut location = format!("{}/{}", parent, urlencoding::encode(route));
+    if let Some(query) = uri.query() {
+        location.push('?');
+        location.push_str(query);
+    }
+    let err = e_code!(ErrorCode::MovedPermanently);
+    match HeaderValue::from_str(&location) {
+        Ok(value) => err.with_header(header::LOCATION, value),
+        Err(_) => err,
+    }
+}
+
+/// Recomputes what is derived from `content`, `target` supplies the stored
+/// values the payload leaves out.
+pub fn fill_derived(am: &mut post::ActiveModel, target: Option<&post::Model>) {
+    let content = match &am.content {
+        ActiveValue::Set(v) => v.clone(),
+        _ => target.and_then(|v| v.content.clone()),
+    };
+    let excerpts = match &am.excerpts {
+        ActiveValue::Set(v) => v.clone(),
+        _ => target.and_then(|v| v.excerpts.clone()),
+    };
+    // content stored before the block schema may not parse, it counts as empty
+    let doc = content
+        .and_then(|v| Document::from_value(&v).ok())
+        .unwrap_or_default();
+    let plain = text::render(&doc);
+    let Stats {
+        word_count,
+        reading_time,
+    } = stats::count(&plain);
+    am.word_count = ActiveValue::Set(word_count);
+    am.reading_time = ActiveValue::Set(reading_time);
+    am.content_text = ActiveValue::Set(Some(plain));
+    am.auto_excerpts = ActiveValue::Set(match excerpts {
+        Some(v) if !v.trim().is_empty() => None,
+        _ => stats::excerpt(&doc),
+    });
+}
+
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    /// a token from `POST /posts/:id/previews`
+    pub preview: Option<String>,
+}
+
+/// Posts which are not public only show to callers who can view private
+/// posts, or who bring a preview token minted for this very post. Others are
+/// told the post does not exist.
+pub async fn ensure_visible(
+    db: &impl ConnectionTrait,
+    w_claims: &WeekClaims,
+    item: &post::Model,
+    preview: Option<&str>,
+) -> Result<(), AppError> {
+    if item.is_public() || w_claims.can(Permission::ViewPrivate) {
+        return Ok(());
+    }
+    let token = match preview {
+        Some(v) if item.status != Some(post::PostStatus::Trashed) => v,
+        _ => return e_code_err!(ErrorCode::NotFound),
+    };
+    match preview_token::find_by_hash(db, item.id, &sha256_hex(token)).await? {
+        Some(v) if v.revoked.is_some() => e_code_err!(ErrorCode::RevokedToken),
+        Some(v) if v.is_expired() => e_code_err!(ErrorCode::ExpiredToken),
+        Some(_) => Ok(()),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+/// The previous and next post in the series of the post. Readers only count
+/// public posts, the post itself always counts.
+pub async fn series_nav(
+    db: &impl ConnectionTrait,
+    item: &post::Model,
+    txs: &[taxonomy::Model],
+    is_authed: bool,
+) -> Result<Option<SeriesNav>, DbErr> {
+    let series = match txs
+        .iter()
+        .find(|tx| tx.t_type == taxonomy::TaxonomyType::Series)
+    {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let visible = if is_authed {
+        Condition::all().add(post::Column::Status.ne(post::PostStatus::Trashed))
+    } else {
+        post::public_condition()
+    };
+    let items = post_taxonomy::series_posts(
+        db,
+        series.id,
+        Condition::any()
+            .add(visible)
+            .add(post::Column::Id.eq(item.id)),
+    )
+    .await?;
+    let index = match items.iter().position(|v| v.id == item.id) {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let mut nav = SeriesNav {
+        part: index + 1,
+        total: items.len(),
+        previous: None,
+        next: None,
+    };
+    if let Some(v) = index.checked_sub(1).and_then(|i| items.get(i)) {
+        nav.previous = Some(summary(db, v.clone(), is_authed).await?);
+    }
+    if let Some(v) = items.get(index + 1) {
+        nav.next = Some(summary(db, v.clone(), is_authed).await?);
+    }
+    Ok(Some(nav))
+}
+
+pub async fn summary(
+    db: &impl ConnectionTrait,
+    mut item: post::Model,
+    is_authed: bool,
+) -> Result<SimplePost, DbErr> {
+    item.comment_count = item.comment_count(db).await?;
+    let mut item = SimplePost::from(item);
+    item.is_authed = is_authed;
+    Ok(item)
+}
+
+/// Takes `isPage`, `parentId` and `sortOrder` from the payload, which
+/// `ActiveModel::from_json` misses as it looks for snake_cased keys.
+pub fn read_page_fields(
+    jv: &serde_json::Value,
+    am: &mut post::ActiveModel,
+) -> Result<(), AppError> {
+    if let Some(v) = jv.get("isPage") {
+        am.is_page = ActiveValue::Set(serde_json::from_value(v.clone())?);
+    }
+    if let Some(v) = jv.get("parentId") {
+        am.parent_id = ActiveValue::Set(serde_json::from_value(v.clone())?);
+    }
+    if let Some(v) = jv.get("sortOrder") {
+        am.sort_order = ActiveValue::Set(serde_json::from_value(v.clone())?);
+    }
+    Ok(())
+}
+
+/// A page nests below an existing page, which must not be the page itself or
+/// one below it. A post which is no page can not have a parent.
+pub async fn check_page_parent(
+    db: &impl ConnectionTrait,
+    am: &post::ActiveModel,
+    target: Option<&post::Model>,
+) -> Result<(), AppError> {
+    let parent = match &am.parent_id {
+        ActiveValue::Set(v) => *v,
+        _ => target.and_then(|v| v.parent_id),
+    };
+    let is_page = match &am.is_page {
+        ActiveValue::Set(v) => *v,
+        _ => target.and_then(|v| v.is_page),
+    };
+    let parent = match parent {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    if is_page != Some(true) {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("only pages can have a parent.".to_owned())
+        );
+    }
+    let id = target.map(|v| v.id);
+    let mut seen = HashSet::new();
+    let mut cursor = Some(parent);
+    while let Some(current) = cursor {
+        if Some(current) == id {
+            return e_code_err!(
+                ErrorCode::InvalidRequest,
+                Some("a page can not be placed below itself.".to_owned())
+            );
+        }
+        if !seen.insert(current) {
+            break;
+        }
+        let item = post::Entity::find_by_id(current).one(db).await?;
+        cursor = match item {
+            Some(v) if v.is_page == Some(true) => v.parent_id,
+            _ if current == parent => {
+                return e_code_err!(
+                    ErrorCode::InvalidRequest,
+                    Some("the parent page does not exist.".to_owned())
+                )
+            }
+            _ => None,
+        };
+    }
+    Ok(())
+}
+
+/// Rejects a `content` which does not follow the block schema, `null` clears it.
+pub fn validate_payload(jv: &serde_json::Value) -> Result<(), AppError> {
+    match jv.get("content").filter(|v| !v.is_null()) {
+        Some(content) => validate::validate(content).map_err(AppError::validation),
+        None => Ok(()),
+    }
+}
+
+/// Authors may only touch their own posts, editors and admins may touch all.
+pub fn ensure_editable(claims: &Claims, item: &post::Model) -> Result<(), AppError> {
+    if claims.can(Permission::EditOthersPosts) || item.author_id == Some(claims.sub) {
+        return Ok(());
+    }
+    e_code_err!(
+        ErrorCode::Forbidden,
+        Some("you can only edit your own posts.".to_owned())
+    )
+}
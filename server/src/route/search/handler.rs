@@ -0,0 +1,91 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::Extension;
+use sea_orm::{
+    ColumnTrait, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    error::ErrorCode,
+    response::{HandlerResult, PaginationData},
+    AppState,
+};
+use crate::dto::post::{Headline, PostWithTaxonomy, SearchHit, SimplePost};
+use crate::entity::post;
+use crate::extract::{Pagination, Permission, Query, WeekClaims};
+use crate::route::post::Filter;
+use crate::utils::search;
+use crate::{e_code_err, res_ok};
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+}
+
+#[derive(FromQueryResult)]
+struct Hit {
+    id: i64,
+    rank: f32,
+    title: String,
+    content: String,
+}
+
+/// Ranked full-text search, the other `get_posts` filters still apply.
+pub async fn search(
+    w_claims: WeekClaims,
+    Extension(state): Extension<Arc<AppState>>,
+    Pagination { page, per }: Pagination,
+    Query(SearchQuery { q }): Query<SearchQuery>,
+    Query(filter): Query<Filter>,
+) -> HandlerResult<impl Serialize> {
+    let q = q.trim();
+    if q.is_empty() {
+        return e_code_err!(ErrorCode::InvalidRequest, Some("q is required.".to_owned()));
+    }
+    let is_authed = w_claims.can(Permission::ViewPrivate);
+    let paginator = post::Entity::find()
+        .select_only()
+        .column(post::Column::Id)
+        .column_as(search::rank(q), "rank")
+        .column_as(search::title_headline(q), "title")
+        .column_as(search::content_headline(q), "content")
+        .filter(filter.condition(is_authed))
+        .filter(search::matches(q))
+        .order_by_desc(search::rank(q))
+        .order_by_desc(post::Column::Id)
+        .into_model::<Hit>()
+        .paginate(&state.db, per);
+
+    let total = paginator.num_items().await?;
+    let pages = paginator.num_pages().await?;
+    let hits = paginator.fetch_page(page).await?;
+    let mut posts: HashMap<i64, post::Model> = post::Entity::find()
+        .filter(post::Column::Id.is_in(hits.iter().map(|hit| hit.id)))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|item| (item.id, item))
+        .collect();
+    let mut formatted = Vec::with_capacity(hits.len());
+    for hit in hits.into_iter() {
+        let mut item = match posts.remove(&hit.id) {
+            Some(item) => item,
+            None => continue,
+        };
+        let txs = item.txs(&state.db).await?;
+        item.comment_count = item.comment_count(&state.db).await?;
+        let mut item = SimplePost::from(item);
+        item.is_authed = is_authed;
+        formatted.push(SearchHit {
+            post: PostWithTaxonomy::from_unclassified(item, txs),
+            rank: hit.rank,
+            headline: Headline {
+                title: search::mark(&hit.title),
+                content: search::mark(&hit.content),
+            },
+        });
+    }
+
+    res_ok!(PaginationData::new(formatted, total, pages))
+}
@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+
+mod handler;
+
+use handler::search;
+
+pub fn get_router() -> Router {
+    Router::new().route("/", get(search))
+}
@@ -1,55 +1,138 @@
 use crate::{
     core::{
-        error::ErrorCode,
-        response::{HandlerResult, PaginationData},
+        error::{AppError, ErrorCode},
+        response::{HandlerResult, PaginationData, TaggedResult},
         AppState,
     },
+    dto::taxonomy::{CategoryList, CategoryNode},
     e_code_err,
-    extract::{Claims, JsonPayload, Pagination, Path},
-    res_ok,
+    extract::{perm, IfMatch, JsonPayload, Pagination, Path, Query, RequirePermission},
+    res_ok, res_tagged,
 };
 use axum::Extension;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder,
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
 };
-use std::sync::Arc;
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc};
 
+use crate::entity::menu_item::{self, MenuItemKind};
 use crate::entity::taxonomy::{self, Model as Taxonomy, TaxonomyType};
 
-pub async fn get_taxonomies(
-    Extension(state): Extension<Arc<AppState>>,
+/// Only categories nest, under an existing category which is not the
+/// category itself or one below it. `id` is `None` for a new taxonomy.
+async fn check_parent(
+    db: &impl ConnectionTrait,
+    id: Option<i32>,
+    parent: Option<i32>,
+    t_type: &TaxonomyType,
+) -> Result<(), AppError> {
+    let parent = match parent {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    if *t_type != TaxonomyType::Category {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("only categories can have a parent.".to_owned())
+        );
+    }
+    // locked so two moves can not close a loop between them
+    let parents: HashMap<i32, Option<i32>> = taxonomy::Entity::find()
+        .filter(taxonomy::Column::TType.eq(TaxonomyType::Category))
+        .lock_exclusive()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|item| (item.id, item.parent_id))
+        .collect();
+    if !parents.contains_key(&parent) {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("the parent category does not exist.".to_owned())
+        );
+    }
+    let mut cursor = Some(parent);
+    for _ in 0..=parents.len() {
+        match cursor {
+            Some(v) if Some(v) == id => {
+                return e_code_err!(
+                    ErrorCode::InvalidRequest,
+                    Some("a category can not be placed below itself.".to_owned())
+                )
+            }
+            Some(v) => cursor = parents.get(&v).copied().flatten(),
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+async fn page_of(
+    db: &impl ConnectionTrait,
     Pagination { page, per }: Pagination,
     t_type: TaxonomyType,
-) -> HandlerResult<PaginationData<Vec<Taxonomy>>> {
+) -> Result<PaginationData<Vec<Taxonomy>>, AppError> {
     let paginator = taxonomy::Entity::find()
         .filter(taxonomy::Column::TType.eq(t_type.clone()))
         .order_by_desc(taxonomy::Column::Id)
-        .paginate(&state.db, per);
+        .paginate(db, per);
 
     let total = paginator.num_items().await?;
     let pages = paginator.num_pages().await?;
     let items = paginator.fetch_page(page).await?;
-    res_ok!(PaginationData::new(items, total, pages))
+    Ok(PaginationData::new(items, total, pages))
+}
+
+pub async fn get_taxonomies(
+    Extension(state): Extension<Arc<AppState>>,
+    pagination: Pagination,
+    t_type: TaxonomyType,
+) -> HandlerResult<PaginationData<Vec<Taxonomy>>> {
+    res_ok!(page_of(&state.db, pagination, t_type).await?)
+}
+
+#[derive(Deserialize)]
+pub struct CategoryQuery {
+    pub flat: Option<String>,
+}
+
+/// The whole category tree, or a page of categories as before with `?flat`.
+pub async fn get_categories(
+    Extension(state): Extension<Arc<AppState>>,
+    pagination: Pagination,
+    Query(CategoryQuery { flat }): Query<CategoryQuery>,
+) -> HandlerResult<CategoryList> {
+    if flat.is_some() {
+        let page = page_of(&state.db, pagination, TaxonomyType::Category).await?;
+        return res_ok!(CategoryList::Flat(page));
+    }
+    let items = taxonomy::Entity::find()
+        .filter(taxonomy::Column::TType.eq(TaxonomyType::Category))
+        .order_by_asc(taxonomy::Column::Id)
+        .all(&state.db)
+        .await?;
+    res_ok!(CategoryList::Tree(CategoryNode::tree(items)))
 }
 
 pub async fn get_taxonomy(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
     t_type: TaxonomyType,
-) -> HandlerResult<Taxonomy> {
+) -> TaggedResult<Taxonomy> {
     let res = taxonomy::Entity::find_by_id(id)
         .filter(taxonomy::Column::TType.eq(t_type))
         .one(&state.db)
         .await?;
     match res {
-        Some(item) => res_ok!(item),
+        Some(item) => res_tagged!(item.etag(), item),
         None => e_code_err!(ErrorCode::NotFound),
     }
 }
 
 pub async fn create_taxonomy(
-    _claims: Claims,
+    _claims: RequirePermission<perm::ManageTaxonomies>,
     Extension(state): Extension<Arc<AppState>>,
     JsonPayload(jv): JsonPayload<serde_json::Value>,
     t_type: TaxonomyType,
@@ -66,20 +149,37 @@ pub async fn create_taxonomy(
 
     am.t_type = sea_orm::ActiveValue::Set(t_type.clone());
 
-    res_ok!(am.insert(&state.db).await?)
+    let txn = state.db.begin().await?;
+    check_parent(&txn, None, am.parent_id.clone().take().flatten(), &t_type).await?;
+    let item = am.insert(&txn).await?;
+    txn.commit().await?;
+    res_ok!(item)
 }
 
 pub async fn update_taxonomy(
-    _claims: Claims,
+    _claims: RequirePermission<perm::ManageTaxonomies>,
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
+    if_match: IfMatch,
     JsonPayload(jv): JsonPayload<serde_json::Value>,
     t_type: TaxonomyType,
-) -> HandlerResult<Taxonomy> {
+) -> TaggedResult<Taxonomy> {
     let mut am = taxonomy::ActiveModel::from_json(jv)?;
     am.id = ActiveValue::Set(id);
-    if !taxonomy::is_exist_in_id(id, t_type, &state.db).await? {
-        return e_code_err!(ErrorCode::NotFound);
+    let txn = state.db.begin().await?;
+    let current = taxonomy::Entity::find_by_id(id)
+        .filter(taxonomy::Column::TType.eq(t_type.clone()))
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+    let current = match current {
+        Some(current) => current,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    if_match.check(&current.etag())?;
+    am.version = ActiveValue::Set(current.version + 1);
+    if let Some(parent) = am.parent_id.clone().take() {
+        check_parent(&txn, Some(id), parent, &t_type).await?;
     }
     if let Some(name) = am.name.clone().take() {
         let repeated = taxonomy::Entity::find()
@@ -88,7 +188,7 @@ pub async fn update_taxonomy(
                     .eq(name.clone())
                     .and(taxonomy::Column::Id.ne(id)),
             )
-            .one(&state.db)
+            .one(&txn)
             .await?;
         if repeated.is_some() {
             return e_code_err!(
@@ -100,18 +200,36 @@ pub async fn update_taxonomy(
             );
         }
     }
-    res_ok!(am.update(&state.db).await?)
+    let item = am.update(&txn).await?;
+    txn.commit().await?;
+    res_tagged!(item.etag(), item)
 }
 
 pub async fn delete_taxonomy(
-    _claims: Claims,
+    _claims: RequirePermission<perm::ManageTaxonomies>,
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
     t_type: TaxonomyType,
 ) -> HandlerResult<()> {
-    taxonomy::Entity::delete_by_id(id)
+    let txn = state.db.begin().await?;
+    let item = taxonomy::Entity::find_by_id(id)
         .filter(taxonomy::Column::TType.eq(t_type))
-        .exec(&state.db)
+        .one(&txn)
         .await?;
+    if let Some(item) = item {
+        // the children move up to take its place
+        taxonomy::Entity::update_many()
+            .col_expr(taxonomy::Column::ParentId, Expr::value(item.parent_id))
+            .col_expr(
+                taxonomy::Column::Version,
+                Expr::col(taxonomy::Column::Version).add(1),
+            )
+            .filter(taxonomy::Column::ParentId.eq(id))
+            .exec(&txn)
+            .await?;
+        menu_item::detach(&txn, MenuItemKind::Taxonomy, id as i64).await?;
+        taxonomy::Entity::delete_by_id(id).exec(&txn).await?;
+    }
+    txn.commit().await?;
     res_ok!(())
 }
@@ -3,11 +3,11 @@ use std::sync::Arc;
 
 use crate::{
     core::{
-        response::{HandlerResult, PaginationData},
+        response::{HandlerResult, PaginationData, TaggedResult},
         AppState,
     },
     entity::taxonomy::{Model as Taxonomy, TaxonomyType},
-    extract::{Claims, JsonPayload, Pagination, Path},
+    extract::{perm, IfMatch, JsonPayload, Pagination, Path, RequirePermission},
 };
 
 use super::curl::{
@@ -30,7 +30,7 @@ macro_rules! get_item_handler {
         pub async fn $name(
             state: Extension<Arc<AppState>>,
             path: Path<i32>,
-        ) -> HandlerResult<Taxonomy> {
+        ) -> TaggedResult<Taxonomy> {
             Ok(get_taxonomy(state, path, $t_type).await?)
         }
     };
@@ -39,7 +39,7 @@ macro_rules! get_item_handler {
 macro_rules! get_create_handler {
     ($name: ident, $t_type: expr) => {
         pub async fn $name(
-            claims: Claims,
+            claims: RequirePermission<perm::ManageTaxonomies>,
             state: Extension<Arc<AppState>>,
             payload: JsonPayload<serde_json::Value>,
         ) -> HandlerResult<Taxonomy> {
@@ -51,12 +51,13 @@ macro_rules! get_create_handler {
 macro_rules! get_update_handler {
     ($name: ident, $t_type: expr) => {
         pub async fn $name(
-            claims: Claims,
+            claims: RequirePermission<perm::ManageTaxonomies>,
             state: Extension<Arc<AppState>>,
             path: Path<i32>,
+            if_match: IfMatch,
             payload: JsonPayload<serde_json::Value>,
-        ) -> HandlerResult<Taxonomy> {
-            Ok(update_taxonomy(claims, state, path, payload, $t_type).await?)
+        ) -> TaggedResult<Taxonomy> {
+            Ok(update_taxonomy(claims, state, path, if_match, payload, $t_type).await?)
         }
     };
 }
@@ -64,7 +65,7 @@ macro_rules! get_update_handler {
 macro_rules! get_delete_handler {
     ($name: ident, $t_type: expr) => {
         pub async fn $name(
-            claims: Claims,
+            claims: RequirePermission<perm::ManageTaxonomies>,
             state: Extension<Arc<AppState>>,
             path: Path<i32>,
         ) -> HandlerResult<()> {
@@ -73,7 +74,6 @@ macro_rules! get_delete_handler {
     };
 }
 
-get_list_handler!(get_categories, TaxonomyType::Category);
 get_list_handler!(get_tags, TaxonomyType::Tag);
 get_list_handler!(get_series, TaxonomyType::Series);
 
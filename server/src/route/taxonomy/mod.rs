@@ -2,12 +2,15 @@ use axum::{routing::get, Router};
 
 mod curl;
 mod handler;
+mod series;
 
+use curl::get_categories;
 use handler::{
     create_category, create_series, create_tag, delete_category, delete_series, delete_tag,
-    get_categories, get_series, get_tags, get_the_category, get_the_series, get_the_tag,
-    update_category, update_series, update_tag,
+    get_series, get_tags, get_the_category, get_the_series, get_the_tag, update_category,
+    update_series, update_tag,
 };
+use series::{get_series_posts, reorder_series_posts};
 
 pub fn get_router() -> Router {
     Router::new()
@@ -28,4 +31,8 @@ pub fn get_router() -> Router {
             "/series/:id",
             get(get_the_series).put(update_series).delete(delete_series),
         )
+        .route(
+            "/series/:id/posts",
+            get(get_series_posts).put(reorder_series_posts),
+        )
 }
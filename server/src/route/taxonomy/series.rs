@@ -0,0 +1,99 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::Extension;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::{
+    core::{
+        error::{AppError, ErrorCode},
+        response::HandlerResult,
+        AppState,
+    },
+    dto::post::SimplePost,
+    e_code_err,
+    entity::{
+        post, post_taxonomy,
+        taxonomy::{self, TaxonomyType},
+    },
+    extract::{perm, JsonPayload, Path, Permission, RequirePermission, WeekClaims},
+    res_ok,
+    route::post::summary,
+};
+
+async fn ensure_series(db: &impl ConnectionTrait, id: i32) -> Result<(), AppError> {
+    let item = taxonomy::Entity::find_by_id(id)
+        .filter(taxonomy::Column::TType.eq(TaxonomyType::Series))
+        .one(db)
+        .await?;
+    match item {
+        Some(_) => Ok(()),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+async fn summaries(
+    db: &impl ConnectionTrait,
+    items: Vec<post::Model>,
+    is_authed: bool,
+) -> Result<Vec<SimplePost>, AppError> {
+    let mut formatted = Vec::with_capacity(items.len());
+    for item in items.into_iter() {
+        formatted.push(summary(db, item, is_authed).await?);
+    }
+    Ok(formatted)
+}
+
+/// The posts of the series in reading order.
+pub async fn get_series_posts(
+    w_claims: WeekClaims,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> HandlerResult<Vec<SimplePost>> {
+    ensure_series(&state.db, id).await?;
+    let is_authed = w_claims.can(Permission::ViewPrivate);
+    let cond = if is_authed {
+        Condition::all().add(post::Column::Status.ne(post::PostStatus::Trashed))
+    } else {
+        post::public_condition()
+    };
+    let items = post_taxonomy::series_posts(&state.db, id, cond).await?;
+    res_ok!(summaries(&state.db, items, is_authed).await?)
+}
+
+#[derive(Deserialize)]
+pub struct ReorderPayload {
+    /// every post of the series, in the new order
+    pub ids: Vec<i64>,
+}
+
+pub async fn reorder_series_posts(
+    _claims: RequirePermission<perm::ManageTaxonomies>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+    JsonPayload(ReorderPayload { ids }): JsonPayload<ReorderPayload>,
+) -> HandlerResult<Vec<SimplePost>> {
+    let txn = state.db.begin().await?;
+    ensure_series(&txn, id).await?;
+    let linked: HashSet<i64> = post_taxonomy::Entity::find()
+        .filter(post_taxonomy::Column::TaxonomyId.eq(id))
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|item| item.post_id)
+        .collect();
+    let given: HashSet<i64> = ids.iter().copied().collect();
+    if given.len() != ids.len() || given != linked {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("ids must list every post of the series once.".to_owned())
+        );
+    }
+    post_taxonomy::reorder(&txn, id, &ids).await?;
+    let items = post_taxonomy::series_posts(&txn, id, Condition::all()).await?;
+    let items = summaries(&txn, items, true).await?;
+    txn.commit().await?;
+    res_ok!(items)
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use chrono::{DateTime, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{error::ErrorCode, response::HandlerResult, AppState};
+use crate::entity::api_token::{self, TokenScope};
+use crate::extract::{auth::API_TOKEN_PREFIX, Claims, JsonPayload, Path, Permission};
+use crate::utils::{random_string, sha256_hex};
+use crate::{e_code_err, res_ok};
+
+pub async fn get_tokens(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> HandlerResult<Vec<api_token::Model>> {
+    claims.ensure_session()?;
+    let items = api_token::Entity::find()
+        .filter(api_token::Column::UserId.eq(claims.sub))
+        .order_by_desc(api_token::Column::Created)
+        .all(&state.db)
+        .await?;
+    res_ok!(items)
+}
+
+#[derive(Deserialize)]
+pub struct CreatePayload {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedToken {
+    /// the plain token, it is only ever shown once
+    pub token: String,
+    #[serde(flatten)]
+    pub item: api_token::Model,
+}
+
+pub async fn create_token(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(payload): JsonPayload<CreatePayload>,
+) -> HandlerResult<CreatedToken> {
+    claims.ensure_session()?;
+    if payload.scopes.is_empty() {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("at least one scope is required.".to_owned())
+        );
+    }
+    let token = format!("{}{}", API_TOKEN_PREFIX, random_string(40));
+    let item = api_token::ActiveModel {
+        user_id: ActiveValue::Set(claims.sub),
+        name: ActiveValue::Set(payload.name),
+        token: ActiveValue::Set(sha256_hex(&token)),
+        prefix: ActiveValue::Set(token.chars().take(8).collect()),
+        scopes: ActiveValue::Set(serde_json::to_value(&payload.scopes)?),
+        created: ActiveValue::Set(Utc::now()),
+        last_used: ActiveValue::Set(None),
+        expires: ActiveValue::Set(payload.expires),
+        revoked: ActiveValue::Set(None),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await?;
+    res_ok!(CreatedToken { token, item })
+}
+
+pub async fn revoke_token(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<()> {
+    claims.ensure_session()?;
+    let target = match api_token::Entity::find_by_id(id).one(&state.db).await? {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    if target.user_id != claims.sub && !claims.can(Permission::ManageUsers) {
+        return e_code_err!(ErrorCode::NotFound);
+    }
+    let mut am: api_token::ActiveModel = target.into();
+    am.revoked = ActiveValue::Set(Some(Utc::now()));
+    am.update(&state.db).await?;
+    res_ok!(())
+}
@@ -0,0 +1,14 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+mod handler;
+
+use handler::{create_token, get_tokens, revoke_token};
+
+pub fn get_router() -> Router {
+    Router::new()
+        .route("/", get(get_tokens).post(create_token))
+        .route("/:id", delete(revoke_token))
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait, PaginatorTrait, QueryOrder};
+use serde::Deserialize;
+
+use crate::core::{
+    error::ErrorCode,
+    response::{HandlerResult, PaginationData},
+    AppState,
+};
+use crate::entity::{session, user, UserRole};
+use crate::extract::{perm, Claims, JsonPayload, Pagination, Path, Permission, RequirePermission};
+use crate::{e_code, e_code_err, res_ok};
+
+pub async fn get_users(
+    _claims: RequirePermission<perm::ManageUsers>,
+    Extension(state): Extension<Arc<AppState>>,
+    Pagination { page, per }: Pagination,
+) -> HandlerResult<PaginationData<Vec<user::Model>>> {
+    let paginator = user::Entity::find()
+        .order_by_asc(user::Column::Id)
+        .paginate(&state.db, per);
+    let total = paginator.num_items().await?;
+    let pages = paginator.num_pages().await?;
+    let items = paginator.fetch_page(page).await?;
+    res_ok!(PaginationData::new(items, total, pages))
+}
+
+pub async fn get_me(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> HandlerResult<user::Model> {
+    claims.ensure_session()?;
+    match user::Entity::find_by_id(claims.sub).one(&state.db).await? {
+        Some(item) => res_ok!(item),
+        None => e_code_err!(ErrorCode::NotFound),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreatePayload {
+    pub email: String,
+    pub nickname: String,
+    pub password: String,
+    pub role: Option<UserRole>,
+}
+
+pub async fn create_user(
+    _claims: RequirePermission<perm::ManageUsers>,
+    Extension(state): Extension<Arc<AppState>>,
+    JsonPayload(payload): JsonPayload<CreatePayload>,
+) -> HandlerResult<user::Model> {
+    if user::find_by_email(&state.db, &payload.email)
+        .await?
+        .is_some()
+    {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some(format!("the email \"{}\" has been used.", payload.email))
+        );
+    }
+    let item = user::create(
+        &state.db,
+        &payload.email,
+        &payload.nickname,
+        &payload.password,
+        payload.role.unwrap_or(UserRole::Author),
+    )
+    .await?;
+    res_ok!(item)
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePayload {
+    pub nickname: Option<String>,
+    pub password: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+pub async fn update_user(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+    JsonPayload(payload): JsonPayload<UpdatePayload>,
+) -> HandlerResult<user::Model> {
+    claims.ensure_session()?;
+    if (claims.sub != id || payload.role.is_some()) && !claims.can(Permission::ManageUsers) {
+        return e_code_err!(ErrorCode::Forbidden);
+    }
+    let target = match user::Entity::find_by_id(id).one(&state.db).await? {
+        Some(v) => v,
+        None => return e_code_err!(ErrorCode::NotFound),
+    };
+    let mut am: user::ActiveModel = target.into();
+    if let Some(nickname) = payload.nickname {
+        am.nickname = ActiveValue::Set(nickname);
+    }
+    if let Some(password) = payload.password {
+        let hashed = user::hash_password(&password).map_err(|_| e_code!(ErrorCode::UnkownError))?;
+        am.password = ActiveValue::Set(hashed);
+    }
+    if let Some(role) = payload.role {
+        am.role = ActiveValue::Set(role);
+    }
+    am.modified = ActiveValue::Set(Some(Utc::now()));
+    res_ok!(am.update(&state.db).await?)
+}
+
+pub async fn delete_user(
+    claims: RequirePermission<perm::ManageUsers>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<()> {
+    if claims.sub == id {
+        return e_code_err!(
+            ErrorCode::InvalidRequest,
+            Some("you can not delete yourself.".to_owned())
+        );
+    }
+    session::revoke_all(&state.db, id).await?;
+    user::Entity::delete_by_id(id).exec(&state.db).await?;
+    res_ok!(())
+}
+
+pub async fn revoke_sessions(
+    _claims: RequirePermission<perm::ManageUsers>,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> HandlerResult<u64> {
+    res_ok!(session::revoke_all(&state.db, id).await?)
+}
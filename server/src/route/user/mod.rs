@@ -0,0 +1,16 @@
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+
+mod handler;
+
+use handler::{create_user, delete_user, get_me, get_users, revoke_sessions, update_user};
+
+pub fn get_router() -> Router {
+    Router::new()
+        .route("/", get(get_users).post(create_user))
+        .route("/me", get(get_me))
+        .route("/:id", put(update_user).delete(delete_user))
+        .route("/:id/revoke-sessions", post(revoke_sessions))
+}
@@ -0,0 +1,85 @@
+//! Empties the trash of posts and comments trashed longer ago than the
+//! `trash_retention_days` site option, 30 days unless set; `0` keeps the trash.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, ColumnTrait, DbErr, EntityTrait, QueryFilter, TransactionTrait};
+
+use crate::{
+    core::AppState,
+    entity::{
+        comment::{self, CommentStatus},
+        post::{self, PostStatus},
+        site_option,
+    },
+};
+
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+pub const RETENTION_OPTION: &str = "trash_retention_days";
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+async fn retention_days(state: &AppState) -> Result<i64, DbErr> {
+    let value = site_option::get_value(&state.db, RETENTION_OPTION).await?;
+    Ok(value
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+/// Deletes what is due and returns how many posts and comments went.
+pub async fn purge_trash(state: &AppState) -> Result<(usize, usize), DbErr> {
+    let days = retention_days(state).await?;
+    // items trashed before `trashed_at` existed start their retention now
+    post::Entity::update_many()
+        .col_expr(post::Column::TrashedAt, Expr::value(Utc::now()))
+        .filter(post::Column::Status.eq(PostStatus::Trashed))
+        .filter(post::Column::TrashedAt.is_null())
+        .exec(&state.db)
+        .await?;
+    comment::Entity::update_many()
+        .col_expr(comment::Column::TrashedAt, Expr::value(Utc::now()))
+        .filter(comment::Column::Status.eq(CommentStatus::Trashed))
+        .filter(comment::Column::TrashedAt.is_null())
+        .exec(&state.db)
+        .await?;
+    if days <= 0 {
+        return Ok((0, 0));
+    }
+    let cutoff = Utc::now() - chrono::Duration::days(days);
+    let due = post::Entity::find()
+        .filter(post::Column::Status.eq(PostStatus::Trashed))
+        .filter(post::Column::TrashedAt.lt(cutoff))
+        .all(&state.db)
+        .await?;
+    let posts = due.len();
+    // one transaction per item, a failure leaves the item whole for next time
+    for item in due.into_iter() {
+        let txn = state.db.begin().await?;
+        post::purge(&txn, item.id).await?;
+        txn.commit().await?;
+    }
+    let due = comment::Entity::find()
+        .filter(comment::Column::Status.eq(CommentStatus::Trashed))
+        .filter(comment::Column::TrashedAt.lt(cutoff))
+        .all(&state.db)
+        .await?;
+    let comments = due.len();
+    for item in due.into_iter() {
+        let txn = state.db.begin().await?;
+        comment::purge(&txn, item.id).await?;
+        txn.commit().await?;
+    }
+    Ok((posts, comments))
+}
+
+pub fn spawn(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = purge_trash(&state).await {
+                eprintln!("purge: {}", err);
+            }
+        }
+    })
+}
@@ -0,0 +1,53 @@
+//! Promotes scheduled posts to `Published` once their publish time is due.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, ColumnTrait, DbErr, EntityTrait, QueryFilter};
+
+use crate::{
+    core::{event::Event, AppState},
+    entity::post::{self, PostStatus},
+};
+
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// Publishes the due posts and returns how many were promoted.
+pub async fn publish_due(state: &AppState) -> Result<usize, DbErr> {
+    let due = post::Entity::find()
+        .filter(post::Column::Status.eq(PostStatus::Scheduled))
+        .filter(post::Column::Published.lte(Utc::now()))
+        .all(&state.db)
+        .await?;
+    let mut count = 0;
+    for item in due.into_iter() {
+        // guarded by the status so a post unscheduled meanwhile is left alone
+        let res = post::Entity::update_many()
+            .col_expr(post::Column::Status, Expr::value(PostStatus::Published))
+            .col_expr(post::Column::Modified, Expr::value(Utc::now()))
+            .filter(post::Column::Id.eq(item.id))
+            .filter(post::Column::Status.eq(PostStatus::Scheduled))
+            .exec(&state.db)
+            .await?;
+        if res.rows_affected > 0 {
+            state.emit(Event::PostPublished {
+                id: item.id,
+                route: item.route,
+            });
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+pub fn spawn(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = publish_due(&state).await {
+                eprintln!("scheduler: {}", err);
+            }
+        }
+    })
+}
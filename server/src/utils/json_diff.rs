@@ -0,0 +1,89 @@
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Op {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// JSON pointer (RFC 6901) of the changed value
+    pub path: String,
+    pub op: Op,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Walks both documents and reports the leaves that differ. Objects are
+/// compared by key and arrays by index; a value whose type changed is reported
+/// as a whole.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    walk(String::new(), old, new, &mut changes);
+    changes
+}
+
+fn walk(path: String, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, av) in a.iter() {
+                let sub = format!("{}/{}", path, escape(key));
+                match b.get(key) {
+                    Some(bv) => walk(sub, av, bv, changes),
+                    None => changes.push(Change {
+                        path: sub,
+                        op: Op::Removed,
+                        old: Some(av.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, bv) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                changes.push(Change {
+                    path: format!("{}/{}", path, escape(key)),
+                    op: Op::Added,
+                    old: None,
+                    new: Some(bv.clone()),
+                });
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let sub = format!("{}/{}", path, index);
+                match (a.get(index), b.get(index)) {
+                    (Some(av), Some(bv)) => walk(sub, av, bv, changes),
+                    (Some(av), None) => changes.push(Change {
+                        path: sub,
+                        op: Op::Removed,
+                        old: Some(av.clone()),
+                        new: None,
+                    }),
+                    (None, Some(bv)) => changes.push(Change {
+                        path: sub,
+                        op: Op::Added,
+                        old: None,
+                        new: Some(bv.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (a, b) if a != b => changes.push(Change {
+            path,
+            op: Op::Changed,
+            old: Some(a.clone()),
+            new: Some(b.clone()),
+        }),
+        _ => {}
+    }
+}
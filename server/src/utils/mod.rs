@@ -1,4 +1,11 @@
+pub mod json_diff;
+pub mod search;
+pub mod slug;
+pub mod totp;
+
+use rand::{distributions::Alphanumeric, Rng};
 use serde_enum_str::Deserialize_enum_str;
+use sha2::{Digest, Sha256};
 
 #[derive(Debug, Clone, Deserialize_enum_str)]
 #[serde(rename_all = "camelCase")]
@@ -21,3 +28,15 @@ impl SqlOrder {
         }
     }
 }
+
+pub fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+pub fn sha256_hex(input: &str) -> String {
+    format!("{:x}", Sha256::digest(input.as_bytes()))
+}
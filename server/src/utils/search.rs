@@ -0,0 +1,110 @@
+//! Full-text search over posts with PostgreSQL `tsvector`s.
+//!
+//! `DOCUMENT` is both the expression of the GIN index and the one queried, the
+//! two must stay identical for the planner to use the index.
+
+use crate::content::{html::escape, text, Document};
+use crate::entity::post;
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Statement,
+};
+
+/// `simple` keeps words as they are, which works for any space separated
+/// language at the cost of stemming. It does not segment CJK text, a run of
+/// CJK characters only matches as a whole.
+const DOCUMENT: &str = "(setweight(to_tsvector('simple', coalesce(title, '')), 'A') \
+    || setweight(to_tsvector('simple', coalesce(subtitle, '')), 'B') \
+    || setweight(to_tsvector('simple', coalesce(excerpts, '')), 'B') \
+    || setweight(to_tsvector('simple', coalesce(content_text, '')), 'C'))";
+
+const QUERY: &str = "websearch_to_tsquery('simple', $1)";
+
+/// private use characters, swapped for `<mark>` once the rest is escaped
+const START_SEL: char = '\u{E000}';
+const STOP_SEL: char = '\u{E001}';
+
+/// Stores the text of posts saved before it was derived on save.
+async fn fill_content_text(db: &impl ConnectionTrait) -> Result<(), DbErr> {
+    let items = post::Entity::find()
+        .filter(post::Column::ContentText.is_null())
+        .all(db)
+        .await?;
+    for item in items {
+        let doc = item
+            .content
+            .as_ref()
+            .and_then(|v| Document::from_value(v).ok())
+            .unwrap_or_default();
+        post::Entity::update_many()
+            .col_expr(post::Column::ContentText, Expr::value(text::render(&doc)))
+            .filter(post::Column::Id.eq(item.id))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn ensure_index(db: &impl ConnectionTrait) -> Result<(), DbErr> {
+    fill_content_text(db).await?;
+    // `posts_search_idx` indexed the raw block JSON, it no longer matches
+    let statements = [
+        "DROP INDEX IF EXISTS posts_search_idx".to_owned(),
+        format!(
+            "CREATE INDEX IF NOT EXISTS posts_search_text_idx ON posts USING GIN ({})",
+            DOCUMENT
+        ),
+    ];
+    for sql in statements {
+        db.execute(Statement::from_string(db.get_database_backend(), sql))
+            .await?;
+    }
+    Ok(())
+}
+
+pub fn matches(keyword: &str) -> SimpleExpr {
+    Expr::cust_with_values(&format!("{} @@ {}", DOCUMENT, QUERY), vec![keyword])
+}
+
+pub fn rank(keyword: &str) -> SimpleExpr {
+    Expr::cust_with_values(&format!("ts_rank({}, {})", DOCUMENT, QUERY), vec![keyword])
+}
+
+fn headline_options(fragments: bool) -> String {
+    let mut options = format!("StartSel={}, StopSel={}", START_SEL, STOP_SEL);
+    if fragments {
+        options.push_str(", MaxFragments=3, MaxWords=24, MinWords=8");
+    } else {
+        options.push_str(", HighlightAll=true");
+    }
+    options
+}
+
+pub fn title_headline(keyword: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        &format!(
+            "ts_headline('simple', title, {}, '{}')",
+            QUERY,
+            headline_options(false)
+        ),
+        vec![keyword],
+    )
+}
+
+pub fn content_headline(keyword: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        &format!(
+            "ts_headline('simple', coalesce(excerpts, '') || ' ' || coalesce(content_text, ''), {}, '{}')",
+            QUERY,
+            headline_options(true)
+        ),
+        vec![keyword],
+    )
+}
+
+/// Escapes a headline for HTML and wraps the matched words in `<mark>`.
+pub fn mark(headline: &str) -> String {
+    escape(headline)
+        .replace(START_SEL, "<mark>")
+        .replace(STOP_SEL, "</mark>")
+}
@@ -0,0 +1,24 @@
+use deunicode::deunicode;
+
+const MAX_LEN: usize = 80;
+
+/// Turns a title into a URL-safe slug, transliterating non-latin scripts
+/// first, e.g. `你好，世界` becomes `ni-hao-shi-jie`.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in deunicode(title).chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= MAX_LEN {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        return "post".to_owned();
+    }
+    slug.to_owned()
+}